@@ -0,0 +1,188 @@
+use crate::{
+    interval::Interval,
+    ray::Ray,
+    vec3::{Point, Vector},
+};
+
+const GAMMA3: f32 = 3.0 * f32::EPSILON * 0.5 / (1.0 - 3.0 * f32::EPSILON * 0.5);
+
+#[derive(Clone, Copy)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        Self { x, y, z }.pad_to_minimums()
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            x: Interval::empty(),
+            y: Interval::empty(),
+            z: Interval::empty(),
+        }
+    }
+
+    pub fn from_points(a: Point, b: Point) -> Self {
+        Self::new(
+            Interval::new(a.x.min(b.x), a.x.max(b.x)),
+            Interval::new(a.y.min(b.y), a.y.max(b.y)),
+            Interval::new(a.z.min(b.z), a.z.max(b.z)),
+        )
+    }
+
+    pub fn surrounding(a: Self, b: Self) -> Self {
+        Self {
+            x: Interval::enclosing(a.x, b.x),
+            y: Interval::enclosing(a.y, b.y),
+            z: Interval::enclosing(a.z, b.z),
+        }
+    }
+
+    pub fn axis(&self, n: usize) -> Interval {
+        match n {
+            1 => self.y,
+            2 => self.z,
+            _ => self.x,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.min > self.x.max || self.y.min > self.y.max || self.z.min > self.z.max
+    }
+
+    pub fn centroid(&self) -> Point {
+        Point::new(
+            (self.x.min + self.x.max) * 0.5,
+            (self.y.min + self.y.max) * 0.5,
+            (self.z.min + self.z.max) * 0.5,
+        )
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let (x, y, z) = (self.x.size(), self.y.size(), self.z.size());
+        if x > y && x > z {
+            0
+        } else if y > z {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let (x, y, z) = (self.x.size(), self.y.size(), self.z.size());
+        2.0 * (x * y + y * z + z * x)
+    }
+
+    pub fn hit(&self, ray: &Ray, interval: &Interval) -> bool {
+        let inverse = Vector::new(
+            1.0 / ray.direction.x,
+            1.0 / ray.direction.y,
+            1.0 / ray.direction.z,
+        );
+        self.hit_inverse(ray.origin, inverse, interval)
+    }
+
+    /// Slab test against a precomputed reciprocal ray direction, so BVH
+    /// traversal only pays for the division once per ray.
+    pub(crate) fn hit_inverse(&self, origin: Point, inverse: Vector, interval: &Interval) -> bool {
+        let mut tmin = interval.min;
+        let mut tmax = interval.max;
+
+        for n in 0..3 {
+            let ax = self.axis(n);
+            let t0 = (ax.min - origin[n]) * inverse[n];
+            let t1 = (ax.max - origin[n]) * inverse[n];
+
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            // Widen the exit distance by the worst-case rounding error of the
+            // three operations above, so flat boxes (e.g. around quads) far
+            // from the origin aren't missed in f32.
+            let t1 = t1 * (1.0 + 2.0 * GAMMA3);
+            // `max`/`min` discard NaN from 0 * inf, which keeps axis-parallel
+            // rays grazing a slab boundary from poisoning the test.
+            tmin = t0.max(tmin);
+            tmax = t1.min(tmax);
+
+            if tmax < tmin {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn pad_to_minimums(self) -> Self {
+        let δ = 0.0001;
+        let pad = |i: Interval| if i.size() < δ { i.expand(δ) } else { i };
+        Self {
+            x: pad(self.x),
+            y: pad(self.y),
+            z: pad(self.z),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit() {
+        let b = Aabb::from_points(Point::new(-1., -1., -1.), Point::new(1., 1., 1.));
+        let i = Interval::new(0.001, f32::INFINITY);
+
+        let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.), 0.0);
+        assert!(b.hit(&r, &i));
+
+        let r = Ray::new(Point::new(0., 2., -5.), Vector::new(0., 0., 1.), 0.0);
+        assert!(!b.hit(&r, &i));
+
+        let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., -1.), 0.0);
+        assert!(!b.hit(&r, &i));
+
+        let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.), 0.0);
+        assert!(!b.hit(&r, &Interval::new(0.001, 3.0)));
+    }
+
+    #[test]
+    fn hit_thin_box_far_from_origin() {
+        let b = Aabb::from_points(Point::new(0., 0., 555.), Point::new(555., 555., 555.));
+        let r = Ray::new(
+            Point::new(278., 278., -800.),
+            Vector::new(0.01, 0.02, 1.),
+            0.0,
+        );
+
+        assert!(b.hit(&r, &Interval::new(0.001, f32::INFINITY)));
+    }
+
+    #[test]
+    fn surrounding() {
+        let a = Aabb::from_points(Point::new(0., 0., 0.), Point::new(1., 1., 1.));
+        let b = Aabb::from_points(Point::new(-1., 2., 0.5), Point::new(0.5, 3., 2.));
+        let s = Aabb::surrounding(a, b);
+
+        assert_eq!(s.x.min, -1.);
+        assert_eq!(s.x.max, 1.);
+        assert_eq!(s.y.min, 0.);
+        assert_eq!(s.y.max, 3.);
+        assert_eq!(s.z.min, 0.);
+        assert_eq!(s.z.max, 2.);
+        assert_eq!(s.longest_axis(), 1);
+    }
+
+    #[test]
+    fn surface_area() {
+        let b = Aabb::from_points(Point::new(0., 0., 0.), Point::new(1., 2., 3.));
+
+        assert_eq!(b.surface_area(), 22.0);
+        assert_eq!(Aabb::empty().surface_area(), 0.0);
+    }
+}
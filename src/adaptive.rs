@@ -0,0 +1,138 @@
+use crate::{color::Color, framebuffer::Framebuffer, tonemap::luminance};
+
+/// Below this mean luminance, errors are measured against it instead, so
+/// that near-black pixels don't chase a vanishing relative error.
+const DARK: f32 = 1e-3;
+
+/// Stops sampling a pixel once its estimate is good enough. The camera's
+/// `samples_per_pixel` is the most any pixel gets.
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptiveSampling {
+    /// Samples every pixel takes before its error is trusted. The error is
+    /// checked again after each further batch of this many.
+    pub min_samples: u32,
+    /// The largest acceptable standard error of a pixel's mean luminance,
+    /// relative to the mean.
+    pub threshold: f32,
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        Self {
+            min_samples: 16,
+            threshold: 0.01,
+        }
+    }
+}
+
+impl AdaptiveSampling {
+    /// Whether a pixel with these samples so far can stop. One that has
+    /// seen only black never does: a rare bright path may be all it's
+    /// missing.
+    pub fn converged(&self, stats: &PixelStats) -> bool {
+        let batch = self.min_samples.max(1);
+        stats.count >= batch
+            && stats.count.is_multiple_of(batch)
+            && stats.mean() > 0.0
+            && stats.relative_error() <= self.threshold
+    }
+}
+
+/// Running mean and variance of a pixel's sample luminance (Welford's
+/// method).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PixelStats {
+    pub count: u32,
+    mean: f32,
+    m2: f32,
+}
+
+impl PixelStats {
+    pub fn add(&mut self, color: Color) {
+        let y = luminance(color);
+        self.count += 1;
+        let δ = y - self.mean;
+        self.mean += δ / self.count as f32;
+        self.m2 += δ * (y - self.mean);
+    }
+
+    pub fn mean(&self) -> f32 {
+        self.mean
+    }
+
+    /// The sample variance.
+    pub fn variance(&self) -> f32 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        self.m2 / (self.count - 1) as f32
+    }
+
+    /// The standard error of the mean over the mean.
+    pub fn relative_error(&self) -> f32 {
+        if self.count == 0 {
+            return f32::INFINITY;
+        }
+        (self.variance() / self.count as f32).sqrt() / self.mean.abs().max(DARK)
+    }
+}
+
+/// An image of how many samples each pixel took, as a fraction of `max`.
+pub fn sample_map(width: u32, height: u32, counts: &[u32], max: u32) -> Framebuffer {
+    let pixels = counts
+        .iter()
+        .map(|&n| {
+            let v = n as f32 / max.max(1) as f32;
+            Color::new(v, v, v)
+        })
+        .collect();
+    Framebuffer {
+        width,
+        height,
+        pixels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn running_variance() {
+        let mut stats = PixelStats::default();
+        for v in [2., 4., 4., 4., 5., 5., 7., 9.] {
+            stats.add(Color::new(v, v, v));
+        }
+        assert!((stats.mean() - 5.0).abs() < 1e-5);
+        assert!((stats.variance() - 32.0 / 7.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn converges_in_batches() {
+        let adaptive = AdaptiveSampling {
+            min_samples: 4,
+            threshold: 0.01,
+        };
+        let mut flat = PixelStats::default();
+        let mut noisy = PixelStats::default();
+        for i in 0..8 {
+            flat.add(Color::white());
+            noisy.add(Color::white() * (i % 2) as f32);
+            assert_eq!(adaptive.converged(&flat), i == 3 || i == 7);
+            assert!(!adaptive.converged(&noisy));
+        }
+    }
+
+    #[test]
+    fn black_pixels_keep_sampling() {
+        let adaptive = AdaptiveSampling {
+            min_samples: 4,
+            threshold: 0.01,
+        };
+        let mut stats = PixelStats::default();
+        for _ in 0..8 {
+            stats.add(Color::black());
+            assert!(!adaptive.converged(&stats));
+        }
+    }
+}
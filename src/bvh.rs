@@ -0,0 +1,337 @@
+use crate::{
+    aabb::Aabb,
+    hit_record::HitRecord,
+    interval::Interval,
+    ray::Ray,
+    vec3::{Point, Vector},
+};
+
+const BUCKETS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+const TRAVERSAL_COST: f32 = 0.125;
+
+/// A flattened bounding volume hierarchy built with the surface area
+/// heuristic. The tree only stores indices, so the same structure can
+/// accelerate anything that can report a bounding box per primitive.
+#[derive(Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+}
+
+enum Node {
+    Leaf {
+        bbox: Aabb,
+        start: usize,
+        count: usize,
+    },
+    // The left child always directly follows its parent in `nodes`.
+    Interior {
+        bbox: Aabb,
+        right: usize,
+        axis: usize,
+    },
+}
+
+struct Primitive {
+    index: usize,
+    bbox: Aabb,
+    centroid: Point,
+}
+
+impl Bvh {
+    pub fn new(boxes: &[Aabb]) -> Self {
+        let mut primitives: Vec<Primitive> = boxes
+            .iter()
+            .enumerate()
+            .map(|(index, bbox)| Primitive {
+                index,
+                bbox: *bbox,
+                centroid: bbox.centroid(),
+            })
+            .collect();
+
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(2 * boxes.len()),
+            indices: Vec::with_capacity(boxes.len()),
+        };
+        if !primitives.is_empty() {
+            bvh.build(&mut primitives);
+        }
+        bvh
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        match self.nodes.first() {
+            Some(Node::Leaf { bbox, .. }) | Some(Node::Interior { bbox, .. }) => *bbox,
+            None => Aabb::empty(),
+        }
+    }
+
+    /// Walks the tree front to back, handing every candidate primitive index
+    /// to `hit_primitive` with an interval that shrinks to the closest hit
+    /// found so far.
+    pub fn hit<'a, F>(
+        &self,
+        ray: &Ray,
+        interval: &Interval,
+        mut hit_primitive: F,
+    ) -> Option<HitRecord<'a>>
+    where
+        F: FnMut(usize, &Ray, &Interval) -> Option<HitRecord<'a>>,
+    {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let inverse = Vector::new(
+            1.0 / ray.direction.x,
+            1.0 / ray.direction.y,
+            1.0 / ray.direction.z,
+        );
+        let negative = [inverse.x < 0.0, inverse.y < 0.0, inverse.z < 0.0];
+
+        let mut closest = *interval;
+        let mut hit_record = None;
+        let mut stack = Vec::with_capacity(64);
+        stack.push(0);
+
+        while let Some(n) = stack.pop() {
+            match &self.nodes[n] {
+                Node::Leaf { bbox, start, count } => {
+                    if !bbox.hit_inverse(ray.origin, inverse, &closest) {
+                        continue;
+                    }
+                    for &index in &self.indices[*start..*start + *count] {
+                        if let Some(rec) = hit_primitive(index, ray, &closest) {
+                            closest.max = rec.t;
+                            hit_record = Some(rec);
+                        }
+                    }
+                }
+                Node::Interior { bbox, right, axis } => {
+                    if !bbox.hit_inverse(ray.origin, inverse, &closest) {
+                        continue;
+                    }
+                    // Push the far child first so the near one is visited
+                    // first and tightens `closest` early.
+                    if negative[*axis] {
+                        stack.push(n + 1);
+                        stack.push(*right);
+                    } else {
+                        stack.push(*right);
+                        stack.push(n + 1);
+                    }
+                }
+            }
+        }
+        hit_record
+    }
+
+    fn build(&mut self, primitives: &mut [Primitive]) -> usize {
+        let bbox = primitives
+            .iter()
+            .fold(Aabb::empty(), |acc, p| Aabb::surrounding(acc, p.bbox));
+        let node = self.nodes.len();
+
+        if primitives.len() == 1 {
+            return self.push_leaf(bbox, primitives);
+        }
+
+        let centroid_bounds = primitives.iter().fold(Aabb::empty(), |acc, p| {
+            let c = p.centroid;
+            let point = Aabb {
+                x: Interval::new(c.x, c.x),
+                y: Interval::new(c.y, c.y),
+                z: Interval::new(c.z, c.z),
+            };
+            Aabb::surrounding(acc, point)
+        });
+        let axis = centroid_bounds.longest_axis();
+        let extent = centroid_bounds.axis(axis);
+
+        if extent.size() <= 0.0 {
+            return self.push_leaf(bbox, primitives);
+        }
+
+        let mid = match split(primitives, axis, extent.min, extent.size(), &bbox) {
+            Some(mid) => mid,
+            None => return self.push_leaf(bbox, primitives),
+        };
+
+        self.nodes.push(Node::Interior {
+            bbox,
+            right: 0,
+            axis,
+        });
+        let (left, right) = primitives.split_at_mut(mid);
+        self.build(left);
+        let right_index = self.build(right);
+        if let Node::Interior { right, .. } = &mut self.nodes[node] {
+            *right = right_index;
+        }
+        node
+    }
+
+    fn push_leaf(&mut self, bbox: Aabb, primitives: &[Primitive]) -> usize {
+        let node = self.nodes.len();
+        self.nodes.push(Node::Leaf {
+            bbox,
+            start: self.indices.len(),
+            count: primitives.len(),
+        });
+        self.indices.extend(primitives.iter().map(|p| p.index));
+        node
+    }
+}
+
+/// Picks the cheapest bucket boundary along `axis` and partitions the
+/// primitives around it. Returns `None` when a leaf would be cheaper.
+fn split(
+    primitives: &mut [Primitive],
+    axis: usize,
+    min: f32,
+    size: f32,
+    bbox: &Aabb,
+) -> Option<usize> {
+    let bucket_of = |p: &Primitive| {
+        let b = (BUCKETS as f32 * (p.centroid[axis] - min) / size) as usize;
+        b.min(BUCKETS - 1)
+    };
+
+    let mut counts = [0usize; BUCKETS];
+    let mut boxes = [Aabb::empty(); BUCKETS];
+    for p in primitives.iter() {
+        let b = bucket_of(p);
+        counts[b] += 1;
+        boxes[b] = Aabb::surrounding(boxes[b], p.bbox);
+    }
+
+    // Sweep from the right to collect the cost of everything above each
+    // boundary, then from the left to finish the SAH cost for every split.
+    let mut above_area = [0.0; BUCKETS - 1];
+    let mut above_count = [0usize; BUCKETS - 1];
+    let mut acc = Aabb::empty();
+    let mut count = 0;
+    for b in (1..BUCKETS).rev() {
+        acc = Aabb::surrounding(acc, boxes[b]);
+        count += counts[b];
+        above_area[b - 1] = acc.surface_area();
+        above_count[b - 1] = count;
+    }
+
+    let mut best = (f32::INFINITY, 0);
+    let mut acc = Aabb::empty();
+    let mut count = 0;
+    for b in 0..BUCKETS - 1 {
+        acc = Aabb::surrounding(acc, boxes[b]);
+        count += counts[b];
+        let cost = count as f32 * acc.surface_area() + above_count[b] as f32 * above_area[b];
+        if cost < best.0 {
+            best = (cost, b);
+        }
+    }
+
+    let area = bbox.surface_area();
+    let leaf_cost = primitives.len() as f32;
+    let split_cost = TRAVERSAL_COST + if area > 0.0 { best.0 / area } else { leaf_cost };
+    if primitives.len() <= MAX_LEAF_SIZE && split_cost >= leaf_cost {
+        return None;
+    }
+
+    let mid = partition(primitives, |p| bucket_of(p) <= best.1);
+    if mid == 0 || mid == primitives.len() {
+        // Every centroid landed on one side of the chosen boundary; fall
+        // back to a median split so the recursion still makes progress.
+        let mid = primitives.len() / 2;
+        primitives
+            .select_nth_unstable_by(mid, |a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+        return Some(mid);
+    }
+    Some(mid)
+}
+
+fn partition<T, F: Fn(&T) -> bool>(items: &mut [T], predicate: F) -> usize {
+    let mut mid = 0;
+    for i in 0..items.len() {
+        if predicate(&items[i]) {
+            items.swap(i, mid);
+            mid += 1;
+        }
+    }
+    mid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::Material};
+
+    fn boxes() -> Vec<Aabb> {
+        (0..100)
+            .map(|i| {
+                let c = Point::new(i as f32 * 3.0, (i % 7) as f32, (i % 3) as f32);
+                Aabb::from_points(c - Vector::new(1., 1., 1.), c + Vector::new(1., 1., 1.))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn visits_every_box_along_the_ray() {
+        let boxes = boxes();
+        let bvh = Bvh::new(&boxes);
+        let ray = Ray::new(Point::new(-10., 0., 0.), Vector::new(1., 0., 0.), 0.0);
+        let interval = Interval::new(0.001, f32::INFINITY);
+
+        let mut visited = vec![];
+        bvh.hit(&ray, &interval, |i, _, _| {
+            visited.push(i);
+            None
+        });
+        visited.sort();
+
+        let expected: Vec<usize> = (0..boxes.len())
+            .filter(|&i| boxes[i].hit(&ray, &interval))
+            .collect();
+        assert!(!expected.is_empty());
+        assert!(expected.iter().all(|i| visited.contains(i)));
+    }
+
+    #[test]
+    fn returns_closest_hit() {
+        let boxes = boxes();
+        let material = Material::Lambertian(Color::white().into());
+        let bvh = Bvh::new(&boxes);
+        let ray = Ray::new(Point::new(-10., 0., 0.), Vector::new(1., 0., 0.), 0.0);
+        let interval = Interval::new(0.001, f32::INFINITY);
+
+        let rec = bvh.hit(&ray, &interval, |i, r, int| {
+            let t = boxes[i].x.min - r.origin.x;
+            if boxes[i].hit(r, int) && int.surrounds(t) {
+                Some(HitRecord::new(
+                    r.at(t),
+                    Vector::new(-1., 0., 0.),
+                    t,
+                    (0.0, 0.0),
+                    r,
+                    &material,
+                ))
+            } else {
+                None
+            }
+        });
+
+        assert_eq!(rec.map(|r| r.t), Some(9.0));
+    }
+
+    #[test]
+    fn empty() {
+        let bvh = Bvh::new(&[]);
+        let ray = Ray::new(Point::new(0., 0., 0.), Vector::new(1., 0., 0.), 0.0);
+
+        assert!(bvh
+            .hit(&ray, &Interval::new(0.001, f32::INFINITY), |_, _, _| None)
+            .is_none());
+        assert!(bvh.bounding_box().is_empty());
+    }
+}
@@ -1,37 +1,95 @@
 use core::f32;
 use indicatif::{ProgressBar, ProgressStyle};
-use rand::prelude::*;
 use rayon::prelude::*;
-use std::{f32::consts::PI, fs::File, io::Write};
+use std::f32::consts::PI;
 
 use crate::{
+    adaptive::{self, AdaptiveSampling, PixelStats},
     color::Color,
+    film::{Film, PixelFilter},
+    framebuffer::{Framebuffer, WriteOptions},
+    hit_record::HitRecord,
     hittable::Hittable,
     interval::Interval,
+    light::power_heuristic,
+    material::transmittance,
+    progress::Progress,
     ray::Ray,
+    sampler::{concentric_disk, PixelSample, Sampler},
+    spectrum::Wavelengths,
+    tonemap::ToneMap,
     vec3::{Point, Vector},
     world::World,
 };
 
+/// What a ray sees when it escapes the scene.
+#[derive(Clone, Copy)]
+pub enum Background {
+    /// Blends from the first color at the bottom to the second at the top.
+    Gradient(Color, Color),
+    Solid(Color),
+    Black,
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Gradient(Color::white(), Color::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Background {
+    pub fn color(self, ray: &Ray) -> Color {
+        match self {
+            Background::Gradient(bottom, top) => {
+                let unit_direction = ray.direction.normalize();
+                let a = 0.5 * (unit_direction.y + 1.0);
+                bottom * (1.0 - a) + top * a
+            }
+            Background::Solid(color) => color,
+            Background::Black => Color::black(),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct Camera {
     pub image_width: u32,
     pub aspect_ratio: f32,
     pub samples_per_pixel: u32,
+    /// Bounces after which every path ends.
     pub max_depth: u32,
+    /// Bounces after which paths are ended at random in proportion to how
+    /// little they can still contribute, or `None` to always trace them to
+    /// `max_depth`.
+    pub russian_roulette_depth: Option<u32>,
+    /// Renders with the same seed are identical.
+    pub seed: u64,
+    pub sampler: Sampler,
+    /// How samples are weighted into the pixels around them.
+    pub filter: PixelFilter,
+    /// Lets pixels stop short of `samples_per_pixel` once they've
+    /// converged.
+    pub adaptive: Option<AdaptiveSampling>,
+    /// Where `render` also saves an image of the samples each pixel took.
+    pub sample_map: Option<String>,
+    /// Traces each sample at a few wavelengths instead of in RGB, so that
+    /// dispersive glass splits light into its colors.
+    pub spectral: bool,
     pub vfov: f32,
     pub lookfrom: Point,
     pub lookat: Point,
     pub vup: Vector,
     pub defocus_angle: f32,
     pub focus_dist: f32,
+    pub background: Background,
+    /// Format settings used by `render` when saving.
+    pub output: WriteOptions,
 
     image_height: u32,
     center: Point,
     pixelδu: Vector,
     pixelδv: Vector,
     pixel00_loc: Point,
-    pixels_sample_scale: f32,
     defocus_disk_u: Vector,
     defocus_disk_v: Vector,
 }
@@ -41,50 +99,77 @@ impl Camera {
         Self::default()
     }
 
+    /// Renders to `filename`, in the format its extension names, with a
+    /// progress bar on the terminal.
     pub fn render(&mut self, world: &World, filename: &str) {
-        self.initialize();
-
-        let mut image = File::create(filename).unwrap();
-
         println!("Writing to {}", filename);
-        writeln!(
-            &mut image,
-            "P3\n{} {}\n255",
-            self.image_width, self.image_height
-        )
-        .unwrap();
-
-        let bar = ProgressBar::new((self.image_width * self.image_height) as u64).with_style(
+
+        let bar = ProgressBar::new(0).with_style(
             ProgressStyle::with_template(
                 "[{elapsed_precise} / {eta_precise}] {bar:50.blue/red} ({percent:>3}%) {pos:>6}/{len:6} ({per_sec})",
             )
             .unwrap(),
         );
 
-        let mut coords: Vec<(u32, u32)> = vec![];
-        for y in 0..self.image_height {
-            for x in 0..self.image_width {
-                coords.push((x, y));
-            }
+        let (fb, counts) = self.render_with_counts(world, &bar);
+        fb.save(filename, &self.output).unwrap();
+        if let Some(path) = &self.sample_map {
+            println!("Writing sample counts to {}", path);
+            // Counts map straight to gray levels, whatever the render's
+            // exposure and tone mapping.
+            let options = WriteOptions {
+                exposure: 0.0,
+                tone_map: ToneMap::Clamp,
+                ..self.output
+            };
+            adaptive::sample_map(fb.width, fb.height, &counts, self.samples_per_pixel)
+                .save(path, &options)
+                .unwrap();
         }
+    }
 
-        let rows: Vec<&mut [(u32, u32)]> = coords.chunks_mut(self.image_width as usize).collect();
+    /// Renders the scene without touching the filesystem or terminal,
+    /// reporting one unit of progress per pixel.
+    pub fn render_to_buffer(&mut self, world: &World, progress: &dyn Progress) -> Framebuffer {
+        self.render_with_counts(world, progress).0
+    }
 
-        let pixels = rows
-            .into_par_iter()
-            .map(|row| self.generate_row(row, world, &bar))
-            .flatten()
-            .collect::<Vec<String>>()
-            .join("\n");
+    /// Like `render_to_buffer`, also returning the number of samples each
+    /// pixel took.
+    pub fn render_with_counts(
+        &mut self,
+        world: &World,
+        progress: &dyn Progress,
+    ) -> (Framebuffer, Vec<u32>) {
+        self.initialize();
+        world.build_bvh();
+        progress.start((self.image_width * self.image_height) as u64);
 
-        bar.finish();
-        writeln!(&mut image, "{}", pixels).unwrap();
+        // Rows are rendered a band at a time and merged in order, which
+        // bounds the memory held by partial films.
+        const BAND: u32 = 16;
+        let mut film = Film::new(self.image_width, self.image_height, self.filter);
+        let mut counts = Vec::with_capacity((self.image_width * self.image_height) as usize);
+        for band in (0..self.image_height).step_by(BAND as usize) {
+            let rows: Vec<_> = (band..(band + BAND).min(self.image_height))
+                .into_par_iter()
+                .map(|y| self.generate_row(y, &film, world, progress))
+                .collect();
+            for (row, row_counts) in rows {
+                film.merge(&row);
+                counts.extend(row_counts);
+            }
+        }
+
+        progress.finish();
+        (film.resolve(), counts)
     }
 
-    fn get_ray(&self, x: u32, y: u32) -> Ray {
-        let mut rng = rand::thread_rng();
-        let x_offset = rng.gen::<f32>() - 0.5;
-        let y_offset = rng.gen::<f32>() - 0.5;
+    /// A ray through pixel (`x`, `y`), and where on the film it passes.
+    fn get_ray(&self, x: u32, y: u32, sample: &mut PixelSample) -> (Ray, (f32, f32)) {
+        let (x_offset, y_offset) = sample.get_2d();
+        let (x_offset, y_offset) = (x_offset - 0.5, y_offset - 0.5);
+        let lens = sample.get_2d();
         let pixel_sample = self.pixel00_loc
             + self.pixelδu * (x as f32 + x_offset)
             + self.pixelδv * (y as f32 + y_offset);
@@ -92,24 +177,23 @@ impl Camera {
         let origin = if self.defocus_angle <= 0.0 {
             self.center
         } else {
-            self.defocus_disk_sample()
+            self.defocus_disk_sample(lens)
         };
         let direction = pixel_sample - origin;
 
-        Ray::new(origin, direction, rng.gen::<f32>())
+        let film_position = (x as f32 + 0.5 + x_offset, y as f32 + 0.5 + y_offset);
+        (Ray::new(origin, direction, sample.get_1d()), film_position)
     }
 
-    fn defocus_disk_sample(&self) -> Point {
-        let p = Point::random_in_unit_disk();
-        self.center + self.defocus_disk_u * p.x + self.defocus_disk_v * p.y
+    fn defocus_disk_sample(&self, u: (f32, f32)) -> Point {
+        let (x, y) = concentric_disk(u);
+        self.center + self.defocus_disk_u * x + self.defocus_disk_v * y
     }
 
     fn initialize(&mut self) {
         let image_height = (self.image_width as f32 / self.aspect_ratio) as u32;
         self.image_height = if image_height < 1 { 1 } else { image_height };
 
-        self.pixels_sample_scale = 1.0 / (self.samples_per_pixel as f32);
-
         self.center = self.lookfrom;
 
         let θ = degrees_to_radians(self.vfov);
@@ -137,48 +221,608 @@ impl Camera {
         self.defocus_disk_v = v * defocus_radius;
     }
 
+    /// The samples of row `y`, splatted onto the part of `film` they reach,
+    /// and the number each pixel took.
     fn generate_row(
         &self,
-        row: &mut [(u32, u32)],
+        y: u32,
+        film: &Film,
         world: &World,
-        bar: &ProgressBar,
-    ) -> Vec<String> {
-        row.into_par_iter()
-            .map(|c| self.generate_pixel(c.0, c.1, world, bar))
-            .collect::<Vec<String>>()
+        progress: &dyn Progress,
+    ) -> (Film, Vec<u32>) {
+        let pixels: Vec<_> = (0..self.image_width)
+            .into_par_iter()
+            .map(|x| self.generate_pixel(x, y, film, world, progress))
+            .collect();
+        let mut row = film.reach(0, self.image_width, y, y + 1);
+        let mut counts = Vec::with_capacity(pixels.len());
+        for (pixel, count) in pixels {
+            row.merge(&pixel);
+            counts.push(count);
+        }
+        (row, counts)
     }
 
-    fn generate_pixel(&self, x: u32, y: u32, world: &World, bar: &ProgressBar) -> String {
-        let pixel_color: Color = (0..self.samples_per_pixel)
-            .into_par_iter()
-            .map(|_| {
-                let ray = self.get_ray(x, y);
-                ray_color(&ray, self.max_depth, world)
-            })
-            .reduce(Color::black, |a, b| a + b)
-            * self.pixels_sample_scale;
-        bar.inc(1);
-        pixel_color.to_ppm()
+    /// The pixel's samples splatted onto the part of `film` they reach, and
+    /// how many it took. Each sample's values depend only on the seed,
+    /// pixel and sample index, and everything is summed in a fixed order,
+    /// so the result doesn't depend on which thread ran what.
+    fn generate_pixel(
+        &self,
+        x: u32,
+        y: u32,
+        film: &Film,
+        world: &World,
+        progress: &dyn Progress,
+    ) -> (Film, u32) {
+        let pixel = (y * self.image_width + x) as u64;
+        let mut splats = film.reach(x, x + 1, y, y + 1);
+        let mut stats = PixelStats::default();
+        for index in 0..self.samples_per_pixel {
+            let mut sample = self
+                .sampler
+                .start(self.seed, pixel, index, self.samples_per_pixel);
+            let (ray, (film_x, film_y)) = self.get_ray(x, y, &mut sample);
+            let mut wavelengths = self.spectral.then(|| Wavelengths::sample(sample.get_1d()));
+            let radiance = ray_color(
+                &ray,
+                self.max_depth,
+                self.russian_roulette_depth,
+                world,
+                self.background,
+                &mut sample,
+                wavelengths.as_mut(),
+            );
+            let color = match wavelengths {
+                Some(wavelengths) => wavelengths.to_rgb(radiance),
+                None => radiance,
+            };
+            splats.add_sample(film_x, film_y, color);
+            stats.add(color);
+            if self.adaptive.is_some_and(|a| a.converged(&stats)) {
+                break;
+            }
+        }
+        progress.advance(1);
+        (splats, stats.count)
     }
 }
 
-fn ray_color(ray: &Ray, depth: u32, world: &World) -> Color {
-    if depth == 0 {
+/// Path traces `ray`, sampling a light directly at every non-specular
+/// bounce. Emission found by scattering is weighted against light sampling
+/// using the density with which the previous bounce chose the ray; camera
+/// rays and specular bounces keep it whole.
+///
+/// Once `roulette_depth` bounces have been made, each path continues with
+/// probability equal to its brightest throughput channel, and survivors
+/// are scaled up to compensate. `max_depth` still ends every path.
+///
+/// Given `wavelengths`, the colors the path carries are spectral samples
+/// instead of RGB, and the caller converts the result.
+fn ray_color(
+    ray: &Ray,
+    max_depth: u32,
+    roulette_depth: Option<u32>,
+    world: &World,
+    background: Background,
+    sample: &mut PixelSample,
+    mut wavelengths: Option<&mut Wavelengths>,
+) -> Color {
+    let mut ray = *ray;
+    let mut scatter_pdf: Option<f32> = None;
+    let mut throughput = Color::white();
+    let mut radiance = Color::black();
+    // The absorption coefficients of the dielectrics the path is inside,
+    // innermost last.
+    let mut interiors: Vec<Color> = Vec::new();
+
+    for depth in 0..max_depth {
+        ray.free_flight = sample.get_1d();
+        let absorption = interiors.last().copied().unwrap_or(Color::black());
+        let Some(rec) = world.hit(&ray, &Interval::new(0.001, f32::INFINITY)) else {
+            let lambda = wavelengths.as_deref();
+            radiance = radiance + throughput * spectral(lambda, background.color(&ray));
+            break;
+        };
+        if let Some(lambda) = wavelengths.as_deref_mut() {
+            if rec.material.is_dispersive() && !lambda.is_terminated() {
+                lambda.terminate_secondary();
+                throughput = Color::new(throughput.x, 0.0, 0.0);
+            }
+        }
+        let lambda = wavelengths.as_deref();
+        let hero = lambda.map(Wavelengths::hero);
+        if absorption != Color::black() {
+            let distance = rec.t * ray.direction.magnitude();
+            throughput = throughput * transmittance(spectral(lambda, absorption), distance);
+        }
+
+        let mut emitted = spectral(lambda, rec.material.emitted(&rec));
+        if let Some(pdf) = scatter_pdf {
+            if rec.material.is_emissive() {
+                let light_pdf = world.light_pdf(ray.origin, ray.direction, ray.time);
+                emitted = emitted * power_heuristic(pdf, light_pdf);
+            }
+        }
+        radiance = radiance + throughput * emitted;
+
+        let wo = -ray.direction.normalize();
+        let light = direct_light(&ray, &rec, wo, &interiors, world, sample, lambda);
+        radiance = radiance + throughput * light;
+
+        let uc = sample.get_1d();
+        let u = sample.get_2d();
+        let Some(bsdf) = rec.material.sample(&rec, wo, uc, u, hero) else {
+            break;
+        };
+        throughput = throughput * spectral(lambda, bsdf.weight);
+
+        // Refracting through a dielectric enters its interior, or leaves it
+        // for the one outside.
+        if let Some(interior) = rec.material.interior() {
+            if bsdf.direction.dot(rec.normal) < 0.0 {
+                if rec.front_face {
+                    interiors.push(interior);
+                } else {
+                    interiors.pop();
+                }
+            }
+        }
+
+        let u_survive = sample.get_1d();
+        if roulette_depth.is_some_and(|min| depth + 1 >= min) {
+            let survival = throughput.x.max(throughput.y).max(throughput.z).min(0.95);
+            if survival <= 0.0 || u_survive >= survival {
+                break;
+            }
+            throughput = throughput / survival;
+        }
+
+        ray = Ray::new(rec.p, bsdf.direction, ray.time);
+        scatter_pdf = (!bsdf.is_delta).then_some(bsdf.pdf);
+    }
+
+    radiance
+}
+
+/// Light reflected toward `wo` straight from a randomly chosen light,
+/// weighted against finding that light by scattering. The light is
+/// attenuated on the way by whichever of the path's `interiors` it crosses.
+/// Draws the same values from `sample` even when there's nothing to light,
+/// keeping later bounces on the same dimensions.
+fn direct_light(
+    ray: &Ray,
+    rec: &HitRecord,
+    wo: Vector,
+    interiors: &[Color],
+    world: &World,
+    sample: &mut PixelSample,
+    lambda: Option<&Wavelengths>,
+) -> Color {
+    let (uc, u) = (sample.get_1d(), sample.get_2d());
+    let free_flight = sample.get_1d();
+    // Lights don't scatter, and delta lobes can't be found by sampling
+    // lights, so there's nothing to gather on either.
+    if rec.material.is_delta() || rec.material.is_emissive() {
+        return Color::black();
+    }
+    let lights = world.lights();
+    if lights.is_empty() {
         return Color::black();
     }
+    let light = &lights[((uc * lights.len() as f32) as usize).min(lights.len() - 1)];
+    let Some(direction) = light.sample(rec.p, ray.time, u) else {
+        return Color::black();
+    };
+    let wi = direction.normalize();
 
-    if let Some(rec) = world.hit(ray, &Interval::new(0.001, f32::INFINITY)) {
-        if let Some(scatter) = rec.material.scatter(ray, &rec) {
-            return scatter.attenuation * ray_color(&scatter.scattered, depth - 1, world);
-        } else {
-            return Color::black();
+    let hero = lambda.map(Wavelengths::hero);
+    let f = rec.material.eval(rec, wo, wi, hero);
+    let light_pdf = world.light_pdf(rec.p, direction, ray.time);
+    if f == Color::black() || light_pdf <= 0.0 {
+        return Color::black();
+    }
+
+    // The density is for the direction, whichever light it came from, so
+    // what counts is the emitter the ray reaches first: the chosen light,
+    // or another one in front of it.
+    let mut shadow = Ray::new(rec.p, direction, ray.time);
+    shadow.free_flight = free_flight;
+    let Some(light_rec) = world.hit(&shadow, &Interval::new(0.001, f32::INFINITY)) else {
+        return Color::black();
+    };
+    if !light_rec.material.is_emissive() {
+        return Color::black();
+    }
+
+    let scatter_pdf = rec.material.pdf(rec, wo, wi, hero);
+    let attenuation = transmittance(
+        spectral(lambda, absorption_toward(rec, wi, interiors)),
+        light_rec.t * direction.magnitude(),
+    );
+    let emitted = spectral(lambda, light_rec.material.emitted(&light_rec));
+    spectral(lambda, f) * attenuation * emitted / light_pdf
+        * power_heuristic(light_pdf, scatter_pdf)
+}
+
+/// The absorption coefficient along a ray leaving `rec` toward `wi`:
+/// inside the surface's dielectric if it refracts in, outside it if it
+/// refracts out, and otherwise wherever the path already is.
+fn absorption_toward(rec: &HitRecord, wi: Vector, interiors: &[Color]) -> Color {
+    let mut outward = interiors.iter().rev();
+    let current = outward.next().copied().unwrap_or(Color::black());
+    match rec.material.interior() {
+        Some(interior) if wi.dot(rec.normal) < 0.0 => {
+            if rec.front_face {
+                interior
+            } else {
+                outward.next().copied().unwrap_or(Color::black())
+            }
         }
+        _ => current,
+    }
+}
+
+/// An RGB color as the path carries it: unchanged, or when tracing
+/// wavelengths, its spectrum at them.
+fn spectral(lambda: Option<&Wavelengths>, rgb: Color) -> Color {
+    match lambda {
+        Some(lambda) => lambda.upsample(rgb),
+        None => rgb,
     }
-    let unit_direction = ray.direction.normalize();
-    let a = 0.5 * (unit_direction.y + 1.0);
-    Color::white() * (1.0 - a) + Color::new(0.5, 0.7, 1.0) * a
 }
 
 fn degrees_to_radians(degrees: f32) -> f32 {
     degrees * PI / 180.0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dispersion::RefractionIndex,
+        entity::Entity,
+        material::{absorption_coefficient, Material},
+        microfacet::TrowbridgeReitz,
+        progress::NoProgress,
+    };
+
+    #[test]
+    fn backgrounds() {
+        let ray = |y: f32| {
+            Ray::new(
+                Point::new(0., 0., 0.),
+                Vector::new(0., y, 1. - y.abs()),
+                0.0,
+            )
+        };
+        let (bottom, top) = (Color::new(1., 0.5, 0.), Color::new(0., 0.5, 1.));
+        let gradient = Background::Gradient(bottom, top);
+        assert_eq!(gradient.color(&ray(-1.)), bottom);
+        assert_eq!(gradient.color(&ray(1.)), top);
+        assert_eq!(gradient.color(&ray(0.)), Color::new(0.5, 0.5, 0.5));
+        let red = Color::new(1., 0., 0.);
+        assert_eq!(Background::Solid(red).color(&ray(0.3)), red);
+        assert_eq!(Background::Black.color(&ray(0.3)), Color::black());
+    }
+
+    #[test]
+    fn sees_lights_and_the_background() {
+        let radiance = Color::new(4., 2., 1.);
+        let mut world = World::new();
+        world.add(Entity::sphere(
+            Point::new(0., 0., -5.),
+            1.0,
+            Material::DiffuseLight(radiance),
+        ));
+        let sky = Color::new(0.2, 0.4, 0.6);
+        let mut camera = Camera {
+            image_width: 1,
+            aspect_ratio: 1.0,
+            samples_per_pixel: 4,
+            max_depth: 10,
+            vfov: 1.0,
+            lookat: Point::new(0., 0., -1.),
+            vup: Vector::new(0., 1., 0.),
+            focus_dist: 1.0,
+            background: Background::Solid(sky),
+            ..Default::default()
+        };
+        let fb = camera.render_to_buffer(&world, &NoProgress);
+        assert!((fb.pixel(0, 0) - radiance).magnitude() < 1e-5);
+
+        camera.lookat = Point::new(0., 0., 1.);
+        let fb = camera.render_to_buffer(&world, &NoProgress);
+        assert!((fb.pixel(0, 0) - sky).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn lights_hidden_behind_lights_change_nothing() {
+        // A floor under a 4×4 light, seen from between the two.
+        let light = |y: f32, size: f32| {
+            Entity::quad(
+                Point::new(-size / 2., y, -size / 2.),
+                Vector::new(size, 0., 0.),
+                Vector::new(0., 0., size),
+                Material::DiffuseLight(Color::new(4., 4., 4.)),
+            )
+        };
+        let mut world = World::new();
+        world.add(Entity::quad(
+            Point::new(-50., 0., -50.),
+            Vector::new(100., 0., 0.),
+            Vector::new(0., 0., 100.),
+            Material::Lambertian(Color::new(0.5, 0.5, 0.5).into()),
+        ));
+        world.add(light(2.0, 4.0));
+        let mut camera = Camera {
+            image_width: 8,
+            aspect_ratio: 1.0,
+            samples_per_pixel: 64,
+            max_depth: 5,
+            vfov: 30.0,
+            lookfrom: Point::new(0., 1.5, 0.),
+            lookat: Point::new(0., 0., 0.),
+            vup: Vector::new(0., 0., -1.),
+            focus_dist: 1.0,
+            background: Background::Black,
+            ..Default::default()
+        };
+        let lit = mean(&camera.render_to_buffer(&world, &NoProgress));
+
+        // Every floor point in view sees this one only through the first.
+        world.add(light(3.0, 1.0));
+        let hidden = mean(&camera.render_to_buffer(&world, &NoProgress));
+        assert!(lit.x > 0.1);
+        assert!(
+            (lit - hidden).magnitude() < 0.02 * lit.x,
+            "{:?} {:?}",
+            lit,
+            hidden
+        );
+    }
+
+    fn floor_under_the_sky() -> World {
+        let mut world = World::new();
+        world.add(Entity::sphere(
+            Point::new(0., -100.5, -1.),
+            100.0,
+            Material::Lambertian(Color::new(0.7, 0.7, 0.7).into()),
+        ));
+        world.add(Entity::sphere(
+            Point::new(0., 0., -1.),
+            0.5,
+            Material::Lambertian(Color::new(0.8, 0.4, 0.2).into()),
+        ));
+        world
+    }
+
+    fn mean(fb: &Framebuffer) -> Color {
+        fb.pixels.iter().fold(Color::black(), |sum, &c| sum + c) / fb.pixels.len() as f32
+    }
+
+    #[test]
+    fn russian_roulette_is_unbiased() {
+        let world = floor_under_the_sky();
+        let mut camera = Camera {
+            image_width: 16,
+            aspect_ratio: 1.0,
+            samples_per_pixel: 256,
+            max_depth: 50,
+            vfov: 90.0,
+            lookat: Point::new(0., 0., -1.),
+            vup: Vector::new(0., 1., 0.),
+            focus_dist: 1.0,
+            ..Default::default()
+        };
+        let full = mean(&camera.render_to_buffer(&world, &NoProgress));
+        camera.russian_roulette_depth = Some(1);
+        let roulette = mean(&camera.render_to_buffer(&world, &NoProgress));
+        assert!(
+            (full - roulette).magnitude() < 0.01,
+            "{:?} {:?}",
+            full,
+            roulette
+        );
+    }
+
+    #[test]
+    fn max_depth_ends_paths() {
+        let world = floor_under_the_sky();
+        let sky = Color::new(0.2, 0.4, 0.6);
+        let mut camera = Camera {
+            image_width: 8,
+            aspect_ratio: 1.0,
+            samples_per_pixel: 4,
+            max_depth: 0,
+            vfov: 90.0,
+            lookat: Point::new(0., 0., -1.),
+            vup: Vector::new(0., 1., 0.),
+            focus_dist: 1.0,
+            background: Background::Solid(sky),
+            ..Default::default()
+        };
+        let fb = camera.render_to_buffer(&world, &NoProgress);
+        assert!(fb.pixels.iter().all(|&c| c == Color::black()));
+
+        // One bounce sees the sky directly, but nothing it lights: the
+        // scene has no lights to sample, and the bounce off the floor is
+        // never followed.
+        camera.max_depth = 1;
+        let fb = camera.render_to_buffer(&world, &NoProgress);
+        assert_eq!(fb.pixel(0, 0), sky);
+        assert_eq!(fb.pixel(4, 7), Color::black());
+        camera.max_depth = 2;
+        let fb = camera.render_to_buffer(&world, &NoProgress);
+        assert!(fb.pixel(4, 7).x > 0.0);
+    }
+
+    #[test]
+    fn renders_are_repeatable() {
+        let mut world = World::new();
+        world.add(Entity::sphere(
+            Point::new(0., -100.5, -1.),
+            100.0,
+            Material::Lambertian(Color::new(0.5, 0.5, 0.5).into()),
+        ));
+        world.add(Entity::sphere(
+            Point::new(0., 0., -1.),
+            0.5,
+            Material::dielectric(1.5),
+        ));
+        world.add(Entity::constant_medium(
+            Entity::sphere(Point::new(1., 0., -1.), 0.5, Material::dielectric(1.0)),
+            2.0,
+            Color::white().into(),
+        ));
+
+        let mut camera = Camera {
+            image_width: 16,
+            aspect_ratio: 1.0,
+            samples_per_pixel: 4,
+            max_depth: 10,
+            russian_roulette_depth: Some(2),
+            vfov: 90.0,
+            lookat: Point::new(0., 0., -1.),
+            vup: Vector::new(0., 1., 0.),
+            focus_dist: 1.0,
+            defocus_angle: 1.0,
+            ..Default::default()
+        };
+        let first = camera.render_to_buffer(&world, &NoProgress);
+        assert_eq!(camera.render_to_buffer(&world, &NoProgress), first);
+
+        camera.seed = 1;
+        assert_ne!(camera.render_to_buffer(&world, &NoProgress), first);
+    }
+
+    #[test]
+    fn spectral_renders_match_rgb_on_average() {
+        let mut world = World::new();
+        world.add(Entity::sphere(
+            Point::new(0., -100.5, -1.),
+            100.0,
+            Material::Lambertian(Color::new(0.8, 0.3, 0.2).into()),
+        ));
+        world.add(Entity::sphere(
+            Point::new(0., 0., -1.),
+            0.5,
+            Material::Dielectric(RefractionIndex::bk7(), Color::black()),
+        ));
+
+        let mut camera = Camera {
+            image_width: 16,
+            aspect_ratio: 1.0,
+            samples_per_pixel: 64,
+            max_depth: 10,
+            vfov: 90.0,
+            lookat: Point::new(0., 0., -1.),
+            vup: Vector::new(0., 1., 0.),
+            focus_dist: 1.0,
+            ..Default::default()
+        };
+        let mean = |fb: Framebuffer| {
+            fb.pixels.iter().fold(Color::black(), |sum, &c| sum + c) / fb.pixels.len() as f32
+        };
+        let rgb = mean(camera.render_to_buffer(&world, &NoProgress));
+        camera.spectral = true;
+        let spectral = mean(camera.render_to_buffer(&world, &NoProgress));
+        assert!(
+            (rgb - spectral).magnitude() < 0.02,
+            "{:?} {:?}",
+            rgb,
+            spectral
+        );
+    }
+
+    #[test]
+    fn glass_absorbs_along_the_path_inside() {
+        // Index-matched glass, so rays pass straight through: two units of
+        // tinted glass around a clear unit-wide bubble.
+        let tint = Color::new(0.5, 1.0, 0.25);
+        let mut world = World::new();
+        world.add(Entity::sphere(
+            Point::new(0., 0., -5.),
+            1.0,
+            Material::Dielectric(1.0.into(), absorption_coefficient(tint, 1.0)),
+        ));
+        world.add(Entity::sphere(
+            Point::new(0., 0., -5.),
+            0.5,
+            Material::dielectric(1.0),
+        ));
+
+        let mut camera = Camera {
+            image_width: 1,
+            aspect_ratio: 1.0,
+            samples_per_pixel: 4,
+            max_depth: 10,
+            vfov: 0.1,
+            lookat: Point::new(0., 0., -1.),
+            vup: Vector::new(0., 1., 0.),
+            focus_dist: 1.0,
+            background: Background::Solid(Color::white()),
+            ..Default::default()
+        };
+        let pixel = camera.render_to_buffer(&world, &NoProgress).pixel(0, 0);
+        assert!((pixel - tint).magnitude() < 1e-3, "{:?}", pixel);
+    }
+
+    #[test]
+    fn light_through_glass_is_absorbed_on_the_glass_side() {
+        // A small light two units up the z axis, seen through rough tinted
+        // glass lying in the z = 0 plane.
+        let mut world = World::new();
+        world.add(Entity::quad(
+            Point::new(-0.01, -0.01, 2.),
+            Vector::new(0.02, 0., 0.),
+            Vector::new(0., 0.02, 0.),
+            Material::DiffuseLight(Color::new(100., 100., 100.)),
+        ));
+        let absorption = Color::new(0.5, 0.1, 1.0);
+        let glass = |absorption: Color| {
+            Material::RoughDielectric(1.5.into(), TrowbridgeReitz::from_roughness(0.5), absorption)
+        };
+        let (tinted, clear) = (glass(absorption), glass(Color::black()));
+        let ray = Ray::new(Point::new(0., 0., -1.), Vector::new(0., 0., 1.), 0.0);
+        let light = |normal: Vector, material: &Material, interiors: &[Color]| {
+            let rec = HitRecord::new(
+                Point::new(0., 0., 0.),
+                normal,
+                1.0,
+                (0., 0.),
+                &ray,
+                material,
+            );
+            let mut sample = Sampler::Independent.start(0, 0, 0, 1);
+            direct_light(
+                &ray,
+                &rec,
+                -ray.direction,
+                interiors,
+                &world,
+                &mut sample,
+                None,
+            )
+        };
+
+        // Lit from outside, through the glass: the light crosses it.
+        let outside = Vector::new(0., 0., -1.);
+        let unabsorbed = light(outside, &clear, &[]);
+        assert!(unabsorbed.x > 0.0);
+        let expected = unabsorbed * transmittance(absorption, 2.0);
+        let through = light(outside, &tinted, &[]);
+        assert!(
+            (through - expected).magnitude() < 1e-2 * unabsorbed.x,
+            "{:?}",
+            through
+        );
+
+        // Leaving the glass toward the light, it's the air that's crossed.
+        let inside = Vector::new(0., 0., 1.);
+        assert_eq!(
+            light(inside, &tinted, &[absorption]),
+            light(inside, &clear, &[])
+        );
+    }
+}
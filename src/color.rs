@@ -1,4 +1,4 @@
-use crate::{interval::Interval, vec3::Vec3};
+use crate::vec3::Vec3;
 
 pub type Color = Vec3;
 
@@ -10,20 +10,38 @@ impl Color {
     pub fn white() -> Self {
         Color::new(1., 1., 1.)
     }
+}
 
-    pub fn to_ppm(self) -> String {
-        let i = Interval::new(0.000, 0.999);
-        let r = (256.0 * i.clamp(linear_to_gamma(self.x))) as u32;
-        let g = (256.0 * i.clamp(linear_to_gamma(self.y))) as u32;
-        let b = (256.0 * i.clamp(linear_to_gamma(self.z))) as u32;
+/// The sRGB transfer function, from linear light to display encoding.
+pub fn linear_to_srgb(linear: f32) -> f32 {
+    if linear <= 0.003_130_8 {
+        12.92 * linear.max(0.0)
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
 
-        format!("{} {} {}", r, g, b)
+/// Inverse of `linear_to_srgb`, for decoding image textures.
+pub fn srgb_to_linear(encoded: f32) -> f32 {
+    if encoded <= 0.040_45 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
     }
 }
 
-fn linear_to_gamma(linear: f32) -> f32 {
-    if linear > 0.0 {
-        return linear.sqrt();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srgb_round_trip() {
+        assert_eq!(linear_to_srgb(0.0), 0.0);
+        assert!((linear_to_srgb(1.0) - 1.0).abs() < 1e-6);
+        assert!((linear_to_srgb(0.18) - 0.4613).abs() < 1e-4);
+        for i in 0..=100 {
+            let v = i as f32 / 100.0;
+            assert!((srgb_to_linear(linear_to_srgb(v)) - v).abs() < 1e-5);
+        }
     }
-    0.0
 }
@@ -0,0 +1,74 @@
+/// Where the refraction index of dispersive glass is quoted when rendering
+/// in RGB: the helium d line, in nm.
+const D_LINE: f32 = 587.56;
+
+/// A dielectric's index of refraction, by wavelength.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RefractionIndex {
+    Constant(f32),
+    /// n = A + B / λ², with λ in μm.
+    Cauchy(f32, f32),
+    /// n² = 1 + Σ Bᵢ λ² / (λ² - Cᵢ), with λ in μm and C in μm².
+    Sellmeier([f32; 3], [f32; 3]),
+}
+
+impl From<f32> for RefractionIndex {
+    fn from(n: f32) -> Self {
+        RefractionIndex::Constant(n)
+    }
+}
+
+impl RefractionIndex {
+    /// Schott N-BK7, the common borosilicate crown glass.
+    pub fn bk7() -> Self {
+        RefractionIndex::Sellmeier(
+            [1.039_612, 0.231_792_34, 1.010_469_5],
+            [0.006_000_699, 0.020_017_914, 103.560_65],
+        )
+    }
+
+    pub fn diamond() -> Self {
+        RefractionIndex::Sellmeier([0.3306, 4.3356, 0.0], [0.030_625, 0.011_236, 0.0])
+    }
+
+    /// The index at `wavelength` in nm, or at the d line for `None`.
+    pub fn at(self, wavelength: Option<f32>) -> f32 {
+        let λ = wavelength.unwrap_or(D_LINE) / 1000.0;
+        match self {
+            RefractionIndex::Constant(n) => n,
+            RefractionIndex::Cauchy(a, b) => a + b / (λ * λ),
+            RefractionIndex::Sellmeier(b, c) => {
+                let λ2 = λ * λ;
+                let sum: f32 = (0..3).map(|i| b[i] * λ2 / (λ2 - c[i])).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+
+    /// Whether different wavelengths refract differently.
+    pub fn is_dispersive(self) -> bool {
+        !matches!(self, RefractionIndex::Constant(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refraction_indices() {
+        assert_eq!(RefractionIndex::from(1.5).at(Some(400.0)), 1.5);
+        assert!((RefractionIndex::bk7().at(None) - 1.5168).abs() < 1e-4);
+        assert!((RefractionIndex::diamond().at(None) - 2.417).abs() < 2e-3);
+
+        // Blue bends more than red.
+        for n in [
+            RefractionIndex::Cauchy(1.5046, 0.0042),
+            RefractionIndex::bk7(),
+            RefractionIndex::diamond(),
+        ] {
+            assert!(n.is_dispersive());
+            assert!(n.at(Some(450.0)) > n.at(Some(650.0)));
+        }
+    }
+}
@@ -1,14 +1,29 @@
+use std::{f32::consts::PI, sync::Arc};
+
 use crate::{
+    aabb::Aabb,
     hit_record::HitRecord,
     hittable::Hittable,
+    instance::Instance,
     interval::Interval,
     material::Material,
+    medium::ConstantMedium,
+    mesh::Mesh,
+    quad::Quad,
     ray::Ray,
-    vec3::{Point, Vector},
+    texture::Texture,
+    triangle::Triangle,
+    vec3::{Mat4, Point, Vector},
 };
 
 pub enum Entity {
     Sphere(Ray, f32, Material),
+    Quad(Quad, Material),
+    Triangle(Triangle, Material),
+    Mesh(Arc<Mesh>),
+    Group(Vec<Entity>),
+    Instance(Instance),
+    ConstantMedium(ConstantMedium),
 }
 
 impl Entity {
@@ -23,23 +38,139 @@ impl Entity {
     pub fn moving_sphere(center: Point, center2: Point, radius: f32, material: Material) -> Self {
         Entity::Sphere(Ray::new(center, center2 - center, 0.), radius, material)
     }
+
+    pub fn quad(q: Point, u: Vector, v: Vector, material: Material) -> Self {
+        Entity::Quad(Quad::new(q, u, v), material)
+    }
+
+    pub fn triangle(a: Point, b: Point, c: Point, material: Material) -> Self {
+        Entity::Triangle(Triangle::new(a, b, c), material)
+    }
+
+    /// Places a (possibly shared) entity through an affine transform, or
+    /// `None` if the transform isn't invertible.
+    pub fn instance(object: Arc<Entity>, transform: Mat4) -> Option<Self> {
+        Instance::new(object, transform).map(Entity::Instance)
+    }
+
+    /// Applies `transform` after any transform already on this entity,
+    /// folding nested instances into one. `None` if the combined transform
+    /// isn't invertible.
+    pub fn transformed(self, transform: Mat4) -> Option<Self> {
+        match self {
+            Entity::Instance(instance) => {
+                Entity::instance(instance.object.clone(), transform * instance.transform())
+            }
+            entity => Entity::instance(Arc::new(entity), transform),
+        }
+    }
+
+    pub fn translate(self, offset: Vector) -> Option<Self> {
+        self.transformed(Mat4::translation(offset))
+    }
+
+    /// Rotates by `degrees` around `axis` through the origin.
+    pub fn rotate(self, axis: Vector, degrees: f32) -> Option<Self> {
+        self.transformed(Mat4::rotation(axis, degrees))
+    }
+
+    pub fn scale(self, factors: Vector) -> Option<Self> {
+        self.transformed(Mat4::scaling(factors))
+    }
+
+    /// Fills `boundary` with a uniform volume of the given density that
+    /// scatters light isotropically, tinted by `albedo`.
+    pub fn constant_medium(boundary: Entity, density: f32, albedo: Texture) -> Self {
+        Entity::ConstantMedium(ConstantMedium::new(
+            boundary,
+            density,
+            Material::Isotropic(albedo),
+        ))
+    }
+
+    /// An axis-aligned box with opposite corners `a` and `b`, built from six
+    /// outward-facing quads.
+    pub fn cuboid(a: Point, b: Point, material: Material) -> Self {
+        let min = Point::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+        let max = Point::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+        let dx = Vector::new(max.x - min.x, 0., 0.);
+        let dy = Vector::new(0., max.y - min.y, 0.);
+        let dz = Vector::new(0., 0., max.z - min.z);
+
+        Entity::Group(vec![
+            // front, right, back, left, top, bottom
+            Entity::quad(Point::new(min.x, min.y, max.z), dx, dy, material.clone()),
+            Entity::quad(Point::new(max.x, min.y, max.z), -dz, dy, material.clone()),
+            Entity::quad(Point::new(max.x, min.y, min.z), -dx, dy, material.clone()),
+            Entity::quad(Point::new(min.x, min.y, min.z), dz, dy, material.clone()),
+            Entity::quad(Point::new(min.x, max.y, max.z), dx, -dz, material.clone()),
+            Entity::quad(Point::new(min.x, min.y, min.z), dx, dz, material),
+        ])
+    }
 }
 
 impl Hittable for Entity {
-    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        match self {
+            Entity::Sphere(center, radius, mat) => hit_sphere(ray, interval, center, *radius, mat),
+            Entity::Quad(quad, mat) => quad.hit(ray, interval, mat),
+            Entity::Triangle(triangle, mat) => triangle.hit(ray, interval, mat),
+            Entity::Mesh(mesh) => mesh.hit(ray, interval),
+            Entity::Group(entities) => hit_group(ray, interval, entities),
+            Entity::Instance(instance) => instance.hit(ray, interval),
+            Entity::ConstantMedium(medium) => medium.hit(ray, interval),
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
         match self {
-            Entity::Sphere(center, radius, mat) => hit_sphere(ray, interval, center, *radius, *mat),
+            Entity::Sphere(center, radius, _) => sphere_bounding_box(center, *radius),
+            Entity::Quad(quad, _) => quad.bounding_box(),
+            Entity::Triangle(triangle, _) => triangle.bounding_box(),
+            Entity::Mesh(mesh) => mesh.bounding_box(),
+            Entity::Group(entities) => entities.iter().fold(Aabb::empty(), |acc, e| {
+                Aabb::surrounding(acc, e.bounding_box())
+            }),
+            Entity::Instance(instance) => instance.bounding_box(),
+            Entity::ConstantMedium(medium) => medium.bounding_box(),
+        }
+    }
+}
+
+// Moving spheres are boxed over the whole shutter interval, since
+// `Camera::get_ray` draws ray times from [0, 1).
+fn sphere_bounding_box(center: &Ray, radius: f32) -> Aabb {
+    let r = radius.abs();
+    let rvec = Vector::new(r, r, r);
+    let c0 = center.at(0.0);
+    let c1 = center.at(1.0);
+    Aabb::surrounding(
+        Aabb::from_points(c0 - rvec, c0 + rvec),
+        Aabb::from_points(c1 - rvec, c1 + rvec),
+    )
+}
+
+fn hit_group<'a>(ray: &Ray, interval: &Interval, entities: &'a [Entity]) -> Option<HitRecord<'a>> {
+    let mut hit_record = None;
+    let mut closest = interval.max;
+
+    for entity in entities {
+        if let Some(rec) = entity.hit(ray, &Interval::new(interval.min, closest)) {
+            closest = rec.t;
+            hit_record = Some(rec);
         }
     }
+    hit_record
 }
 
-fn hit_sphere(
+pub(crate) fn hit_sphere<'a>(
     ray: &Ray,
     interval: &Interval,
     center: &Ray,
     radius: f32,
-    material: Material,
-) -> Option<HitRecord> {
+    material: &'a Material,
+) -> Option<HitRecord<'a>> {
     let current_center = center.at(ray.time);
     let oc = current_center - ray.origin;
     let a = ray.direction.length_squared();
@@ -65,5 +196,38 @@ fn hit_sphere(
     let t = root;
     let p = ray.at(t);
     let normal = (p - current_center) / radius;
-    Some(HitRecord::new(p, normal, t, ray, material))
+    Some(HitRecord::new(
+        p,
+        normal,
+        t,
+        sphere_uv(normal),
+        ray,
+        material,
+    ))
+}
+
+/// Maps a point on the unit sphere to (u, v), with u running around the
+/// y axis from -x and v running from the bottom pole to the top.
+fn sphere_uv(p: Point) -> (f32, f32) {
+    let θ = (-p.y).clamp(-1.0, 1.0).acos();
+    let φ = (-p.z).atan2(p.x) + PI;
+    (φ / (2.0 * PI), θ / PI)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sphere_uv() {
+        let close = |a: (f32, f32), b: (f32, f32)| {
+            assert!((a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6);
+        };
+
+        close(super::sphere_uv(Point::new(1., 0., 0.)), (0.5, 0.5));
+        close(super::sphere_uv(Point::new(0., 1., 0.)), (0.5, 1.0));
+        close(super::sphere_uv(Point::new(0., 0., 1.)), (0.25, 0.5));
+        close(super::sphere_uv(Point::new(-1., 0., 0.)), (0.0, 0.5));
+        close(super::sphere_uv(Point::new(0., 0., -1.)), (0.75, 0.5));
+    }
 }
@@ -0,0 +1,284 @@
+use std::io::{self, Write};
+
+use crate::{framebuffer::Framebuffer, zlib};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum PixelType {
+    #[default]
+    Half,
+    Float,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Compression {
+    None,
+    /// Deflate over blocks of 16 scanlines.
+    #[default]
+    Zip,
+}
+
+impl PixelType {
+    fn id(self) -> i32 {
+        match self {
+            PixelType::Half => 1,
+            PixelType::Float => 2,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            PixelType::Half => 2,
+            PixelType::Float => 4,
+        }
+    }
+}
+
+impl Compression {
+    fn id(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Zip => 3,
+        }
+    }
+
+    fn lines_per_block(self) -> u32 {
+        match self {
+            Compression::None => 1,
+            Compression::Zip => 16,
+        }
+    }
+}
+
+/// Writes a single-part scanline OpenEXR file with R, G and B channels.
+pub fn write<W: Write>(
+    w: &mut W,
+    fb: &Framebuffer,
+    pixel_type: PixelType,
+    compression: Compression,
+) -> io::Result<()> {
+    let (width, height) = (fb.width, fb.height);
+
+    let mut header = vec![];
+    header.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+    // Channels are listed, and stored, in alphabetical order.
+    let mut channels = vec![];
+    for name in [b'B', b'G', b'R'] {
+        channels.extend_from_slice(&[name, 0]);
+        channels.extend_from_slice(&pixel_type.id().to_le_bytes());
+        // pLinear and three reserved bytes, then x and y sampling.
+        channels.extend_from_slice(&[0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+    }
+    channels.push(0);
+    attribute(&mut header, "channels", "chlist", &channels);
+    attribute(
+        &mut header,
+        "compression",
+        "compression",
+        &[compression.id()],
+    );
+
+    let mut window = vec![];
+    for v in [0, 0, width as i32 - 1, height as i32 - 1] {
+        window.extend_from_slice(&v.to_le_bytes());
+    }
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    // Increasing y.
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    let lines = compression.lines_per_block();
+    let blocks: Vec<Vec<u8>> = fb
+        .pixels
+        .chunks((width * lines) as usize)
+        .enumerate()
+        .map(|(i, block)| {
+            let mut raw = Vec::with_capacity(block.len() * 3 * pixel_type.size());
+            for row in block.chunks(width as usize) {
+                // B, G, R.
+                for channel in [2, 1, 0] {
+                    for color in row {
+                        let v = color[channel];
+                        match pixel_type {
+                            PixelType::Half => raw.extend_from_slice(&to_half(v).to_le_bytes()),
+                            PixelType::Float => raw.extend_from_slice(&v.to_le_bytes()),
+                        }
+                    }
+                }
+            }
+            let data = match compression {
+                Compression::None => raw,
+                Compression::Zip => {
+                    // Readers treat a block no smaller than its raw size
+                    // as uncompressed.
+                    let zipped = zlib::compress(&predict(&interleave(&raw)));
+                    if zipped.len() < raw.len() {
+                        zipped
+                    } else {
+                        raw
+                    }
+                }
+            };
+
+            let mut chunk = Vec::with_capacity(data.len() + 8);
+            chunk.extend_from_slice(&(i as i32 * lines as i32).to_le_bytes());
+            chunk.extend_from_slice(&(data.len() as i32).to_le_bytes());
+            chunk.extend_from_slice(&data);
+            chunk
+        })
+        .collect();
+
+    let mut offset = (header.len() + blocks.len() * 8) as u64;
+    for block in &blocks {
+        header.extend_from_slice(&offset.to_le_bytes());
+        offset += block.len() as u64;
+    }
+    w.write_all(&header)?;
+    for block in &blocks {
+        w.write_all(block)?;
+    }
+    Ok(())
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    for s in [name, kind] {
+        header.extend_from_slice(s.as_bytes());
+        header.push(0);
+    }
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+/// Moves the even bytes to the first half and the odd bytes to the second,
+/// which groups the high and low bytes of each value.
+fn interleave(raw: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(raw.len());
+    out.extend(raw.iter().step_by(2));
+    out.extend(raw.iter().skip(1).step_by(2));
+    out
+}
+
+/// Replaces each byte after the first with its difference from the
+/// previous one, offset by 128.
+fn predict(data: &[u8]) -> Vec<u8> {
+    let mut out = data.to_vec();
+    for i in 1..data.len() {
+        out[i] = data[i].wrapping_sub(data[i - 1]).wrapping_add(128);
+    }
+    out
+}
+
+/// Converts to IEEE 754 half precision, rounding to nearest even.
+fn to_half(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinity stays infinity; NaN keeps a nonzero mantissa.
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        // Subnormal: shift the mantissa, with its implicit leading one,
+        // into place.
+        let m = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        return sign | round_shift(m, shift) as u16;
+    }
+
+    // A carry out of the mantissa correctly bumps the exponent, and up to
+    // infinity if need be.
+    sign | (((exponent as u32) << 10) + round_shift(mantissa, 13)) as u16
+}
+
+fn round_shift(value: u32, shift: u32) -> u32 {
+    let half = 1 << (shift - 1);
+    let remainder = value & ((1 << shift) - 1);
+    let shifted = value >> shift;
+    if remainder > half || (remainder == half && shifted & 1 == 1) {
+        shifted + 1
+    } else {
+        shifted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn half() {
+        assert_eq!(to_half(0.0), 0x0000);
+        assert_eq!(to_half(-0.0), 0x8000);
+        assert_eq!(to_half(1.0), 0x3c00);
+        assert_eq!(to_half(-2.0), 0xc000);
+        assert_eq!(to_half(0.1), 0x2e66);
+        assert_eq!(to_half(65504.0), 0x7bff);
+        assert_eq!(to_half(65520.0), 0x7c00);
+        assert_eq!(to_half(1e10), 0x7c00);
+        assert_eq!(to_half(f32::INFINITY), 0x7c00);
+        assert!(to_half(f32::NAN) & 0x3ff != 0);
+        assert_eq!(to_half(2f32.powi(-24)), 0x0001);
+        assert_eq!(to_half(2f32.powi(-14)), 0x0400);
+        assert_eq!(to_half(1e-10), 0x0000);
+    }
+
+    #[test]
+    fn zip_preprocessing() {
+        assert_eq!(interleave(&[1, 2, 3, 4, 5]), [1, 3, 5, 2, 4]);
+        assert_eq!(predict(&[10, 12, 11, 11]), [10, 130, 127, 128]);
+    }
+
+    #[test]
+    fn layout() {
+        let mut bytes = vec![];
+        let fb = Framebuffer {
+            width: 2,
+            height: 2,
+            pixels: vec![Color::new(1., 2., 3.); 4],
+        };
+        write(&mut bytes, &fb, PixelType::Float, Compression::None).unwrap();
+
+        assert_eq!(&bytes[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+        assert_eq!(&bytes[8..28], b"channels\0chlist\0\x37\0\0\0");
+        // Two single-line blocks of 3 channels x 2 pixels x 4 bytes.
+        let end = bytes.len();
+        let block = |i: usize| &bytes[end - (2 - i) * 32..end - (1 - i) * 32];
+        let offset = |i: usize| {
+            let at = end - 64 - 16 + 8 * i;
+            u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap()) as usize
+        };
+        assert_eq!(offset(0), end - 64);
+        assert_eq!(offset(1), end - 32);
+        assert_eq!(&block(1)[..8], &[1, 0, 0, 0, 24, 0, 0, 0]);
+        assert_eq!(
+            &block(1)[8..16],
+            &[3f32.to_le_bytes(), 3f32.to_le_bytes()].concat()
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use std::f32::consts::PI;
+
+use crate::{color::Color, framebuffer::Framebuffer};
+
+/// How much a sample counts toward each pixel near it, by its offset from
+/// the pixel's center. Each filter is separable and reaches `radius`
+/// pixels from its center along each axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PixelFilter {
+    /// Equal weight within the radius; a radius of 0.5 is the pixel itself.
+    Box(f32),
+    /// Falls off linearly to zero at the radius.
+    Tent(f32),
+    /// A Gaussian with σ a third of the radius, shifted to reach zero there.
+    Gaussian(f32),
+    /// Mitchell-Netravali with B = C = 1/3, stretched over the radius.
+    Mitchell(f32),
+    /// A sinc windowed by a sinc the width of the radius.
+    Lanczos(f32),
+}
+
+impl Default for PixelFilter {
+    fn default() -> Self {
+        PixelFilter::Box(0.5)
+    }
+}
+
+impl PixelFilter {
+    pub fn radius(self) -> f32 {
+        match self {
+            PixelFilter::Box(r)
+            | PixelFilter::Tent(r)
+            | PixelFilter::Gaussian(r)
+            | PixelFilter::Mitchell(r)
+            | PixelFilter::Lanczos(r) => r,
+        }
+    }
+
+    /// The weight of a sample at offset (`x`, `y`) from a pixel's center.
+    pub fn eval(self, x: f32, y: f32) -> f32 {
+        self.eval_1d(x) * self.eval_1d(y)
+    }
+
+    fn eval_1d(self, x: f32) -> f32 {
+        match self {
+            // Half-open, so that a sample on the border between two pixels
+            // only counts toward the one it was taken in.
+            PixelFilter::Box(r) => (-r < x && x <= r) as u8 as f32,
+            PixelFilter::Tent(r) => (r - x.abs()).max(0.0),
+            PixelFilter::Gaussian(r) => {
+                let sigma = r / 3.0;
+                let gaussian = |x: f32| (-x * x / (2.0 * sigma * sigma)).exp();
+                (gaussian(x) - gaussian(r)).max(0.0)
+            }
+            PixelFilter::Mitchell(r) if x.abs() < r => mitchell(2.0 * x.abs() / r),
+            PixelFilter::Lanczos(r) if x.abs() < r => sinc(x) * sinc(x / r),
+            _ => 0.0,
+        }
+    }
+}
+
+fn mitchell(x: f32) -> f32 {
+    const B: f32 = 1.0 / 3.0;
+    const C: f32 = 1.0 / 3.0;
+    let value = if x > 1.0 {
+        (-B - 6.0 * C) * x * x * x
+            + (6.0 * B + 30.0 * C) * x * x
+            + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C)
+    } else {
+        (12.0 - 9.0 * B - 6.0 * C) * x * x * x
+            + (-18.0 + 12.0 * B + 6.0 * C) * x * x
+            + (6.0 - 2.0 * B)
+    };
+    value / 6.0
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-5 {
+        return 1.0;
+    }
+    (PI * x).sin() / (PI * x)
+}
+
+/// The least total weight a pixel needs to be resolved. Filters with
+/// negative lobes can leave a pixel's weights summing to nearly nothing, or
+/// less, and dividing by that would blow its few samples up or flip them.
+const MIN_WEIGHT: f32 = 1e-3;
+
+/// Weighted sums of the samples splatted onto a rectangle of pixels: the
+/// whole image, or the part of it a few samples can reach. Parts are
+/// rendered separately and merged in a fixed order, which keeps the
+/// result independent of scheduling.
+#[derive(Clone, Debug)]
+pub struct Film {
+    pub filter: PixelFilter,
+    x0: u32,
+    y0: u32,
+    width: u32,
+    height: u32,
+    sums: Vec<Color>,
+    weights: Vec<f32>,
+}
+
+impl Film {
+    /// An empty film covering a whole image.
+    pub fn new(width: u32, height: u32, filter: PixelFilter) -> Self {
+        Self::rect(filter, 0, 0, width, height)
+    }
+
+    fn rect(filter: PixelFilter, x0: u32, y0: u32, width: u32, height: u32) -> Self {
+        let len = (width * height) as usize;
+        Self {
+            filter,
+            x0,
+            y0,
+            width,
+            height,
+            sums: vec![Color::black(); len],
+            weights: vec![0.0; len],
+        }
+    }
+
+    /// An empty film covering the part of this one that samples inside
+    /// pixels `x0..x1` by `y0..y1` can reach.
+    pub fn reach(&self, x0: u32, x1: u32, y0: u32, y1: u32) -> Film {
+        let margin = self.filter.radius().ceil() as u32;
+        let left = x0.saturating_sub(margin).max(self.x0);
+        let top = y0.saturating_sub(margin).max(self.y0);
+        let right = (x1 + margin).min(self.x0 + self.width);
+        let bottom = (y1 + margin).min(self.y0 + self.height);
+        Self::rect(
+            self.filter,
+            left,
+            top,
+            right.saturating_sub(left),
+            bottom.saturating_sub(top),
+        )
+    }
+
+    /// Adds a sample taken at (`x`, `y`) in pixel units, where pixel (i, j)
+    /// covers [i, i + 1) × [j, j + 1), to every pixel its filter reaches.
+    pub fn add_sample(&mut self, x: f32, y: f32, color: Color) {
+        let r = self.filter.radius();
+        let range = |c: f32, start: u32, len: u32| {
+            let lo = (c - 0.5 - r).ceil().max(start as f32) as u32;
+            let hi = ((c - 0.5 + r).floor() + 1.0).clamp(0.0, (start + len) as f32) as u32;
+            lo..hi.max(lo)
+        };
+        // The filter is separable, so each column's weight only needs
+        // working out once.
+        let columns = range(x, self.x0, self.width);
+        let wx: Vec<f32> = columns
+            .clone()
+            .map(|i| self.filter.eval_1d(i as f32 + 0.5 - x))
+            .collect();
+        for j in range(y, self.y0, self.height) {
+            let wy = self.filter.eval_1d(j as f32 + 0.5 - y);
+            if wy == 0.0 {
+                continue;
+            }
+            for (i, wx) in columns.clone().zip(&wx) {
+                let w = wx * wy;
+                if w != 0.0 {
+                    let index = ((j - self.y0) * self.width + (i - self.x0)) as usize;
+                    self.sums[index] = self.sums[index] + color * w;
+                    self.weights[index] += w;
+                }
+            }
+        }
+    }
+
+    /// Adds another film's sums, over the part it shares with this one.
+    pub fn merge(&mut self, other: &Film) {
+        for j in 0..other.height {
+            let y = other.y0 + j;
+            if y < self.y0 || y >= self.y0 + self.height {
+                continue;
+            }
+            for i in 0..other.width {
+                let x = other.x0 + i;
+                if x < self.x0 || x >= self.x0 + self.width {
+                    continue;
+                }
+                let from = (j * other.width + i) as usize;
+                let to = ((y - self.y0) * self.width + (x - self.x0)) as usize;
+                self.sums[to] = self.sums[to] + other.sums[from];
+                self.weights[to] += other.weights[from];
+            }
+        }
+    }
+
+    /// Each pixel's weighted average. Pixels no sample reached, or reached
+    /// with too little weight, are black.
+    pub fn resolve(&self) -> Framebuffer {
+        let pixels = self
+            .sums
+            .iter()
+            .zip(&self.weights)
+            .map(|(&sum, &w)| {
+                if w > MIN_WEIGHT {
+                    sum / w
+                } else {
+                    Color::black()
+                }
+            })
+            .collect();
+        Framebuffer {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters() {
+        for filter in [
+            PixelFilter::Box(0.5),
+            PixelFilter::Tent(1.0),
+            PixelFilter::Gaussian(1.5),
+            PixelFilter::Mitchell(2.0),
+            PixelFilter::Lanczos(3.0),
+        ] {
+            let r = filter.radius();
+            assert!(filter.eval(0.0, 0.0) > 0.0);
+            assert_eq!(filter.eval(-r, 0.0), 0.0);
+            assert_eq!(filter.eval(0.2, -0.3), filter.eval(-0.2, 0.3));
+        }
+        // Mitchell-Netravali dips below zero past x = 1 of its native
+        // [-2, 2] support.
+        assert!(PixelFilter::Mitchell(2.0).eval(1.5, 0.0) < 0.0);
+    }
+
+    #[test]
+    fn box_filter_averages_within_the_pixel() {
+        let mut film = Film::new(3, 1, PixelFilter::Box(0.5));
+        film.add_sample(1.0, 0.5, Color::white());
+        film.add_sample(1.99, 0.5, Color::black());
+        film.add_sample(2.5, 0.5, Color::new(0.5, 0.5, 0.5));
+        let fb = film.resolve();
+        assert_eq!(fb.pixel(0, 0), Color::black());
+        assert_eq!(fb.pixel(1, 0), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(fb.pixel(2, 0), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn negative_weights_resolve_to_black() {
+        for filter in [PixelFilter::Mitchell(2.0), PixelFilter::Lanczos(3.0)] {
+            let mut film = Film::new(5, 1, filter);
+            film.add_sample(3.0, 0.5, Color::white());
+            let fb = film.resolve();
+            // Pixel 1 is 1.5 away, in the negative lobe; pixel 2 is next
+            // to the sample.
+            assert_eq!(fb.pixel(1, 0), Color::black());
+            assert!((fb.pixel(2, 0) - Color::white()).magnitude() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn merging_parts_matches_splatting_directly() {
+        let samples = [(0.3, 0.7), (1.5, 1.5), (2.9, 0.1), (3.6, 2.2)];
+        let color = |i: usize| Color::new(i as f32, 1.0, 0.5);
+
+        let mut whole = Film::new(4, 3, PixelFilter::Gaussian(1.5));
+        for (i, &(x, y)) in samples.iter().enumerate() {
+            whole.add_sample(x, y, color(i));
+        }
+
+        let mut merged = Film::new(4, 3, PixelFilter::Gaussian(1.5));
+        for (i, &(x, y)) in samples.iter().enumerate() {
+            let mut part = merged.reach(x as u32, x as u32 + 1, y as u32, y as u32 + 1);
+            part.add_sample(x, y, color(i));
+            merged.merge(&part);
+        }
+
+        assert_eq!(whole.resolve(), merged.resolve());
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    color::{linear_to_srgb, Color},
+    exr, hdr,
+    png::{self, BitDepth},
+    ppm,
+    tonemap::ToneMap,
+};
+
+/// Linear, unclamped radiance for each pixel, stored row by row from the
+/// top.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Framebuffer {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Color>,
+}
+
+/// Settings for the formats that have any. Exposure and tone mapping only
+/// apply to the display formats, PPM and PNG; HDR output keeps the raw
+/// radiance.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct WriteOptions {
+    /// Exposure adjustment in stops.
+    pub exposure: f32,
+    pub tone_map: ToneMap,
+    pub bit_depth: BitDepth,
+    pub exr_pixel_type: exr::PixelType,
+    pub exr_compression: exr::Compression,
+}
+
+impl WriteOptions {
+    /// Exposes, tone maps and sRGB-encodes a pixel into [0, 1].
+    pub fn display(&self, color: Color) -> Color {
+        let c = self.tone_map.apply(color * self.exposure.exp2());
+        Color::new(
+            linear_to_srgb(c.x),
+            linear_to_srgb(c.y),
+            linear_to_srgb(c.z),
+        )
+    }
+}
+
+impl Framebuffer {
+    /// A black image of the given size.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color::black(); (width * height) as usize],
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Color) {
+        self.pixels[(y * self.width + x) as usize] = color;
+    }
+
+    /// Writes the image in the format named by the file extension: `.png`,
+    /// `.hdr`, `.exr`, or PPM for anything else. Fails without touching
+    /// the file if `pixels` doesn't match the dimensions.
+    pub fn save(&self, path: impl AsRef<Path>, options: &WriteOptions) -> io::Result<()> {
+        if self.pixels.len() as u64 != self.width as u64 * self.height as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} pixels for a {}x{} image",
+                    self.pixels.len(),
+                    self.width,
+                    self.height
+                ),
+            ));
+        }
+        let path = path.as_ref();
+        let mut w = BufWriter::new(File::create(path)?);
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("png") => png::write(&mut w, self, options)?,
+            Some("hdr") => hdr::write(&mut w, self)?,
+            Some("exr") => exr::write(
+                &mut w,
+                self,
+                options.exr_pixel_type,
+                options.exr_compression,
+            )?,
+            _ => ppm::write(&mut w, self, options)?,
+        }
+        w.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels() {
+        let mut fb = Framebuffer::new(3, 2);
+        fb.set_pixel(2, 1, Color::white());
+
+        assert_eq!(fb.pixels.len(), 6);
+        assert_eq!(fb.pixel(2, 1), Color::white());
+        assert_eq!(fb.pixels[5], Color::white());
+        assert_eq!(fb.pixel(0, 1), Color::black());
+    }
+
+    #[test]
+    fn refuses_to_save_mismatched_pixels() {
+        let mut fb = Framebuffer::new(3, 2);
+        fb.pixels.pop();
+        let path = std::env::temp_dir().join("rust-tracer-mismatched.ppm");
+        let err = fb.save(&path, &WriteOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn display() {
+        let mut options = WriteOptions::default();
+        let c = options.display(Color::new(2., 1., -1.));
+        assert!((c.x - 1.0).abs() < 1e-6 && (c.y - 1.0).abs() < 1e-6 && c.z == 0.0);
+
+        options.exposure = -1.0;
+        let c = options.display(Color::new(2., 0.36, 0.));
+        assert!((c.x - 1.0).abs() < 1e-6 && (c.y - 0.4613).abs() < 1e-4);
+    }
+}
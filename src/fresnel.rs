@@ -0,0 +1,153 @@
+use std::ops::{Add, Div, Mul, Sub};
+
+use crate::color::Color;
+
+/// Reflectance of a dielectric boundary for light arriving at `cos_i` to
+/// the normal, where `eta` is the index of refraction on the far side over
+/// the near one. A negative `cos_i` arrives from the far side. Total
+/// internal reflection gives 1.
+pub fn dielectric(cos_i: f32, eta: f32) -> f32 {
+    let (cos_i, eta) = if cos_i < 0.0 {
+        (-cos_i, 1.0 / eta)
+    } else {
+        (cos_i, eta)
+    };
+    let cos_i = cos_i.min(1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// Reflectance of a conductor with complex refractive index `eta` + i`k`,
+/// per channel, for light arriving at `cos_i` to the normal. Unpolarized,
+/// so the average of the two polarizations.
+pub fn conductor(cos_i: f32, eta: Color, k: Color) -> Color {
+    Color::new(
+        complex(cos_i, Complex::new(eta.x, k.x)),
+        complex(cos_i, Complex::new(eta.y, k.y)),
+        complex(cos_i, Complex::new(eta.z, k.z)),
+    )
+}
+
+fn complex(cos_i: f32, eta: Complex) -> f32 {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let sin2_i = 1.0 - cos_i * cos_i;
+    let sin2_t = Complex::new(sin2_i, 0.0) / (eta * eta);
+    let cos_t = (Complex::new(1.0, 0.0) - sin2_t).sqrt();
+
+    let cos_i = Complex::new(cos_i, 0.0);
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parallel.norm() + r_perpendicular.norm()) / 2.0
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    /// The squared magnitude.
+    fn norm(self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// The principal square root.
+    fn sqrt(self) -> Self {
+        let n = self.norm().sqrt();
+        if n == 0.0 {
+            return Self::new(0.0, 0.0);
+        }
+        let t1 = (0.5 * (n + self.re.abs())).sqrt();
+        let t2 = 0.5 * self.im / t1;
+        if self.re >= 0.0 {
+            Self::new(t1, t2)
+        } else {
+            Self::new(t2.abs(), t1.copysign(self.im))
+        }
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        let scale = 1.0 / rhs.norm();
+        Self::new(
+            scale * (self.re * rhs.re + self.im * rhs.im),
+            scale * (self.im * rhs.re - self.re * rhs.im),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dielectric_reflectance() {
+        assert!((dielectric(1.0, 1.5) - 0.04).abs() < 1e-6);
+        assert!((dielectric(-1.0, 1.5) - 0.04).abs() < 1e-6);
+        assert_eq!(dielectric(0.0, 1.5), 1.0);
+        // Past the critical angle, about 41.8° inside glass.
+        assert_eq!(dielectric(-0.7, 1.5), 1.0);
+        assert!(dielectric(-0.8, 1.5) < 1.0);
+        // Brewster's angle reflects no parallel polarized light.
+        let brewster = 1.5f32.atan().cos();
+        let s = (1.0 - brewster * brewster).sqrt() / 1.5;
+        let cos_t = (1.0 - s * s).sqrt();
+        let perpendicular = (brewster - 1.5 * cos_t) / (brewster + 1.5 * cos_t);
+        assert!((dielectric(brewster, 1.5) - perpendicular * perpendicular / 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn conductor_reflectance() {
+        // At normal incidence, ((n - 1)² + k²) / ((n + 1)² + k²).
+        let (eta, k) = (Color::new(0.2, 0.9, 1.1), Color::new(3.9, 2.5, 2.1));
+        let f = conductor(1.0, eta, k);
+        let expected = |n: f32, k: f32| ((n - 1.0).powi(2) + k * k) / ((n + 1.0).powi(2) + k * k);
+        assert!((f.x - expected(0.2, 3.9)).abs() < 1e-5);
+        assert!((f.z - expected(1.1, 2.1)).abs() < 1e-5);
+
+        // Everything is reflected at grazing angles.
+        let f = conductor(0.0, eta, k);
+        assert!((f.y - 1.0).abs() < 1e-5);
+
+        // With no absorption, a dielectric's reflectance.
+        let f = conductor(1.0, Color::new(1.5, 1.5, 1.5), Color::black());
+        assert!((f.x - 0.04).abs() < 1e-6);
+    }
+}
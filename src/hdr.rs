@@ -0,0 +1,125 @@
+use std::io::{self, Write};
+
+use crate::{color::Color, framebuffer::Framebuffer};
+
+/// Writes a Radiance RGBE file with run-length encoded scanlines. Negative
+/// components can't be represented and are clamped to zero.
+pub fn write<W: Write>(w: &mut W, fb: &Framebuffer) -> io::Result<()> {
+    let (width, height) = (fb.width, fb.height);
+
+    write!(
+        w,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        height, width
+    )?;
+
+    let mut channels: [Vec<u8>; 4] = Default::default();
+    for row in fb.pixels.chunks(width as usize) {
+        // Run-length encoding is only defined for these widths.
+        if !(8..32768).contains(&width) {
+            for &color in row {
+                w.write_all(&rgbe(color))?;
+            }
+            continue;
+        }
+
+        for channel in channels.iter_mut() {
+            channel.clear();
+        }
+        for &color in row {
+            for (channel, byte) in channels.iter_mut().zip(rgbe(color)) {
+                channel.push(byte);
+            }
+        }
+
+        w.write_all(&[2, 2, (width >> 8) as u8, width as u8])?;
+        for channel in &channels {
+            write_runs(w, channel)?;
+        }
+    }
+    Ok(())
+}
+
+/// Shares the largest component's exponent between all three mantissas.
+fn rgbe(color: Color) -> [u8; 4] {
+    let (r, g, b) = (color.x.max(0.0), color.y.max(0.0), color.z.max(0.0));
+    let max = r.max(g).max(b);
+    if max < 1e-32 || !max.is_finite() {
+        return [0; 4];
+    }
+    let (mantissa, exponent) = frexp(max);
+    let scale = mantissa * 256.0 / max;
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (exponent + 128) as u8,
+    ]
+}
+
+/// Splits `x` into a mantissa in [0.5, 1) and a power of two.
+fn frexp(x: f32) -> (f32, i32) {
+    let mut exponent = x.log2().floor() as i32 + 1;
+    let mut mantissa = x / 2f32.powi(exponent);
+    // log2 can land on the wrong side of a power of two.
+    if mantissa >= 1.0 {
+        mantissa *= 0.5;
+        exponent += 1;
+    } else if mantissa < 0.5 {
+        mantissa *= 2.0;
+        exponent -= 1;
+    }
+    (mantissa, exponent)
+}
+
+/// Encodes runs of three or more equal bytes as a count above 128 followed
+/// by the byte, and everything else as a count followed by literals.
+fn write_runs<W: Write>(w: &mut W, data: &[u8]) -> io::Result<()> {
+    const MIN_RUN: usize = 3;
+    let run_at = |i: usize| {
+        data[i..]
+            .iter()
+            .take(127)
+            .take_while(|&&b| b == data[i])
+            .count()
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let run = run_at(i);
+        if run >= MIN_RUN {
+            w.write_all(&[128 + run as u8, data[i]])?;
+            i += run;
+            continue;
+        }
+
+        let start = i;
+        while i < data.len() && i - start < 128 && run_at(i) < MIN_RUN {
+            i += 1;
+        }
+        w.write_all(&[(i - start) as u8])?;
+        w.write_all(&data[start..i])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgbe() {
+        assert_eq!(super::rgbe(Color::new(1., 0.5, 0.)), [128, 64, 0, 129]);
+        assert_eq!(super::rgbe(Color::new(0., 0., 0.)), [0, 0, 0, 0]);
+        assert_eq!(super::rgbe(Color::new(-1., 3., 0.)), [0, 192, 0, 130]);
+        assert_eq!(frexp(1000.0), (1000.0 / 1024.0, 10));
+    }
+
+    #[test]
+    fn runs() {
+        let mut out = vec![];
+        write_runs(&mut out, &[7, 7, 7, 7, 1, 2, 2, 3]).unwrap();
+
+        assert_eq!(out, [132, 7, 4, 1, 2, 2, 3]);
+    }
+}
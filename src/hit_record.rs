@@ -5,21 +5,32 @@ use crate::{
 };
 
 #[derive(Clone, Copy)]
-pub struct HitRecord {
+pub struct HitRecord<'a> {
     pub p: Point,
     pub normal: Vector,
     pub t: f32,
+    pub u: f32,
+    pub v: f32,
     pub front_face: bool,
-    pub material: Material,
+    pub material: &'a Material,
 }
 
-impl HitRecord {
-    pub fn new(p: Point, normal: Vector, t: f32, ray: &Ray, material: Material) -> Self {
+impl<'a> HitRecord<'a> {
+    pub fn new(
+        p: Point,
+        normal: Vector,
+        t: f32,
+        (u, v): (f32, f32),
+        ray: &Ray,
+        material: &'a Material,
+    ) -> Self {
         let front_face = ray.direction.dot(normal) < 0.0;
         Self {
             p,
             normal: if front_face { normal } else { -normal },
             t,
+            u,
+            v,
             front_face,
             material,
         }
@@ -1,5 +1,6 @@
-use crate::{hit_record::HitRecord, interval::Interval, ray::Ray};
+use crate::{aabb::Aabb, hit_record::HitRecord, interval::Interval, ray::Ray};
 
 pub trait Hittable {
-    fn hit(&self, r: &Ray, i: &Interval) -> Option<HitRecord>;
+    fn hit(&self, r: &Ray, i: &Interval) -> Option<HitRecord<'_>>;
+    fn bounding_box(&self) -> Aabb;
 }
@@ -0,0 +1,305 @@
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::color::{srgb_to_linear, Color};
+
+#[derive(Clone, Copy, Default)]
+pub enum Filter {
+    Nearest,
+    #[default]
+    Bilinear,
+}
+
+/// How texture coordinates outside [0, 1] map back onto the image.
+#[derive(Clone, Copy, Default)]
+pub enum Wrap {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(PathBuf, io::Error),
+    Format(PathBuf, String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            ImageError::Format(path, message) => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl Error for ImageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ImageError::Io(_, err) => Some(err),
+            ImageError::Format(..) => None,
+        }
+    }
+}
+
+/// An image of linear colors, stored row by row from the top.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Image {
+    /// Loads an ASCII (P3) or binary (P6) PPM file, converting its
+    /// sRGB-encoded values to linear color.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImageError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|e| ImageError::Io(path.to_path_buf(), e))?;
+        Self::parse_ppm(&bytes).map_err(|message| ImageError::Format(path.to_path_buf(), message))
+    }
+
+    pub fn parse_ppm(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = PpmReader { bytes, pos: 0 };
+
+        let magic = reader.token()?;
+        let binary = match magic.as_str() {
+            "P3" => false,
+            "P6" => true,
+            _ => return Err(format!("unsupported image format `{}`", magic)),
+        };
+        let width = reader.number()?;
+        let height = reader.number()?;
+        let max = reader.number()?;
+        if width == 0 || height == 0 {
+            return Err(format!("invalid image size {}x{}", width, height));
+        }
+        if max == 0 || max > 65535 {
+            return Err(format!("invalid maximum value {}", max));
+        }
+
+        let count = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| format!("invalid image size {}x{}", width, height))?;
+        // Every sample takes at least a byte, so a header claiming more
+        // than the file holds can't make us reserve more than that.
+        let mut samples = Vec::with_capacity(count.min(bytes.len() - reader.pos));
+        if binary {
+            // Exactly one whitespace byte separates the header from the
+            // raster.
+            reader.pos += 1;
+            let size = if max < 256 { 1 } else { 2 };
+            let raster = count
+                .checked_mul(size)
+                .and_then(|n| n.checked_add(reader.pos))
+                .and_then(|end| reader.bytes.get(reader.pos..end))
+                .ok_or("image data is truncated")?;
+            for chunk in raster.chunks(size) {
+                let value = chunk.iter().fold(0, |acc, &b| (acc << 8) | b as usize);
+                samples.push(value);
+            }
+        } else {
+            for _ in 0..count {
+                samples.push(reader.number()?);
+            }
+        }
+
+        let scale = 1.0 / max as f32;
+        let decode = |value: usize| srgb_to_linear(value.min(max) as f32 * scale);
+        let pixels = samples
+            .chunks(3)
+            .map(|rgb| Color::new(decode(rgb[0]), decode(rgb[1]), decode(rgb[2])))
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Looks up the image at (u, v), with v = 0 at the bottom row.
+    pub fn sample(&self, u: f32, v: f32, filter: Filter, wrap: Wrap) -> Color {
+        // Coordinates far outside [0, 1] wrap or clamp the same way as
+        // nearer ones; bounding them keeps the texel arithmetic in range.
+        let bound = |t: f32| if t.is_nan() { 0.0 } else { t.clamp(-1e6, 1e6) };
+        let (u, v) = (bound(u), bound(v));
+        let x = u * self.width as f32;
+        let y = (1.0 - v) * self.height as f32;
+
+        match filter {
+            Filter::Nearest => {
+                let x = wrap.apply(x.floor() as i64, self.width);
+                let y = wrap.apply(y.floor() as i64, self.height);
+                self.pixel(x, y)
+            }
+            Filter::Bilinear => {
+                // Shift by half a texel so pixel centers land on integer
+                // coordinates.
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let xs = [wrap.apply(x0, self.width), wrap.apply(x0 + 1, self.width)];
+                let ys = [wrap.apply(y0, self.height), wrap.apply(y0 + 1, self.height)];
+
+                let top = self.pixel(xs[0], ys[0]) * (1.0 - fx) + self.pixel(xs[1], ys[0]) * fx;
+                let bottom = self.pixel(xs[0], ys[1]) * (1.0 - fx) + self.pixel(xs[1], ys[1]) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        }
+    }
+}
+
+impl Wrap {
+    fn apply(self, i: i64, size: usize) -> usize {
+        let n = size as i64;
+        let wrapped = match self {
+            Wrap::Repeat => i.rem_euclid(n),
+            Wrap::Clamp => i.clamp(0, n - 1),
+            Wrap::Mirror => {
+                let m = i.rem_euclid(2 * n);
+                if m >= n {
+                    2 * n - 1 - m
+                } else {
+                    m
+                }
+            }
+        };
+        wrapped as usize
+    }
+}
+
+struct PpmReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl PpmReader<'_> {
+    fn token(&mut self) -> Result<String, String> {
+        loop {
+            match self.bytes.get(self.pos) {
+                Some(b'#') => {
+                    while !matches!(self.bytes.get(self.pos), Some(b'\n') | None) {
+                        self.pos += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(_) => break,
+                None => return Err("unexpected end of file".to_string()),
+            }
+        }
+        let start = self.pos;
+        while matches!(self.bytes.get(self.pos), Some(b) if !b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.pos]).into_owned())
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| format!("invalid number `{}`", token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checker() -> Image {
+        Image {
+            width: 2,
+            height: 2,
+            pixels: vec![
+                Color::white(),
+                Color::black(),
+                Color::black(),
+                Color::white(),
+            ],
+        }
+    }
+
+    #[test]
+    fn parse_ascii() {
+        let image = Image::parse_ppm(b"P3\n# comment\n2 1\n255\n255 0 0  0 0 255\n").unwrap();
+
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixel(0, 0), Color::new(1., 0., 0.));
+        assert_eq!(image.pixel(1, 0), Color::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn parse_binary() {
+        let image = Image::parse_ppm(b"P6 1 2 255\n\xff\x00\x00\x00\xff\x00").unwrap();
+
+        assert_eq!((image.width, image.height), (1, 2));
+        assert_eq!(image.pixel(0, 0), Color::new(1., 0., 0.));
+        assert_eq!(image.pixel(0, 1), Color::new(0., 1., 0.));
+
+        assert!(Image::parse_ppm(b"P6 1 2 255\n\xff\x00").is_err());
+        assert!(Image::parse_ppm(b"P5 1 1 255\n\xff").is_err());
+    }
+
+    #[test]
+    fn rejects_impossible_sizes() {
+        let huge = format!("P6 {} {} 255\n\x7f", usize::MAX / 2, 3);
+        assert!(Image::parse_ppm(huge.as_bytes()).is_err());
+        let long = format!("P6 {} 1 65535\n\x7f", usize::MAX / 6);
+        assert!(Image::parse_ppm(long.as_bytes()).is_err());
+        assert!(Image::parse_ppm(b"P3 100000 100000 255\n1 2 3").is_err());
+    }
+
+    #[test]
+    fn wrap() {
+        assert_eq!(Wrap::Repeat.apply(-1, 4), 3);
+        assert_eq!(Wrap::Repeat.apply(5, 4), 1);
+        assert_eq!(Wrap::Clamp.apply(-1, 4), 0);
+        assert_eq!(Wrap::Clamp.apply(5, 4), 3);
+        assert_eq!(Wrap::Mirror.apply(-1, 4), 0);
+        assert_eq!(Wrap::Mirror.apply(4, 4), 3);
+        assert_eq!(Wrap::Mirror.apply(9, 4), 1);
+    }
+
+    #[test]
+    fn sample() {
+        let image = checker();
+
+        assert_eq!(
+            image.sample(0.25, 0.75, Filter::Nearest, Wrap::Repeat),
+            Color::white()
+        );
+        assert_eq!(
+            image.sample(0.75, 0.75, Filter::Nearest, Wrap::Repeat),
+            Color::black()
+        );
+        assert_eq!(
+            image.sample(0.5, 0.5, Filter::Bilinear, Wrap::Clamp),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            image.sample(0.25, 0.75, Filter::Bilinear, Wrap::Clamp),
+            Color::white()
+        );
+        for (u, v) in [
+            (f32::INFINITY, 0.5),
+            (0.5, f32::NEG_INFINITY),
+            (f32::NAN, 1e30),
+        ] {
+            for wrap in [Wrap::Repeat, Wrap::Clamp, Wrap::Mirror] {
+                image.sample(u, v, Filter::Bilinear, wrap);
+                image.sample(u, v, Filter::Nearest, wrap);
+            }
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    entity::Entity,
+    hit_record::HitRecord,
+    hittable::Hittable,
+    interval::Interval,
+    ray::Ray,
+    vec3::{Mat4, Point},
+};
+
+/// Places a shared entity in the world through an affine transform.
+pub struct Instance {
+    pub object: Arc<Entity>,
+    transform: Mat4,
+    inverse: Mat4,
+    normal_transform: Mat4,
+    bbox: Aabb,
+}
+
+impl Instance {
+    /// `None` if `transform` isn't invertible.
+    pub fn new(object: Arc<Entity>, transform: Mat4) -> Option<Self> {
+        let inverse = transform.inverse()?;
+        let bbox = transform_box(&transform, &object.bounding_box());
+        Some(Self {
+            object,
+            transform,
+            inverse,
+            normal_transform: inverse.transpose(),
+            bbox,
+        })
+    }
+
+    pub fn transform(&self) -> Mat4 {
+        self.transform
+    }
+
+    pub fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        // The object-space direction is left unnormalized so that t is the
+        // same in both spaces.
+        let local = Ray {
+            origin: self.inverse.transform_point(ray.origin),
+            direction: self.inverse.transform_vector(ray.direction),
+            ..*ray
+        };
+
+        let mut rec = self.object.hit(&local, interval)?;
+        rec.p = self.transform.transform_point(rec.p);
+        // The object already oriented the normal against the local ray, and
+        // the inverse transpose preserves that orientation.
+        rec.normal = self
+            .normal_transform
+            .transform_vector(rec.normal)
+            .normalize();
+        Some(rec)
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+fn transform_box(m: &Mat4, bbox: &Aabb) -> Aabb {
+    if bbox.is_empty() {
+        return *bbox;
+    }
+    let mut min = Point::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+    let mut max = Point::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for i in 0..8 {
+        let corner = Point::new(
+            if i & 1 == 0 { bbox.x.min } else { bbox.x.max },
+            if i & 2 == 0 { bbox.y.min } else { bbox.y.max },
+            if i & 4 == 0 { bbox.z.min } else { bbox.z.max },
+        );
+        let p = m.transform_point(corner);
+        min = Point::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+        max = Point::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+    }
+    Aabb::from_points(min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::Material, vec3::Vector};
+
+    #[test]
+    fn hit() {
+        let sphere = Arc::new(Entity::sphere(
+            Point::new(0., 0., 0.),
+            1.0,
+            Material::Lambertian(Color::white().into()),
+        ));
+        let m =
+            Mat4::translation(Vector::new(0., 0., -5.)) * Mat4::scaling(Vector::new(1., 2., 1.));
+        let instance = Instance::new(sphere, m).unwrap();
+        let i = Interval::new(0.001, f32::INFINITY);
+
+        let r = Ray::new(Point::new(0., 1.5, 0.), Vector::new(0., 0., -1.), 0.0);
+        let rec = instance.hit(&r, &i).unwrap();
+        assert!((rec.p.z - (-5.0 + 0.4375_f32.sqrt())).abs() < 1e-5);
+        assert!((rec.p.y - 1.5).abs() < 1e-6);
+        assert!(rec.normal.z > 0.0 && rec.normal.y > 0.0);
+        assert!((rec.normal.magnitude() - 1.0).abs() < 1e-6);
+
+        let r = Ray::new(Point::new(0., 2.5, 0.), Vector::new(0., 0., -1.), 0.0);
+        assert!(instance.hit(&r, &i).is_none());
+    }
+
+    #[test]
+    fn rejects_singular_transforms() {
+        let sphere = Arc::new(Entity::sphere(
+            Point::new(0., 0., 0.),
+            1.0,
+            Material::Lambertian(Color::white().into()),
+        ));
+        assert!(Instance::new(sphere.clone(), Mat4::scaling(Vector::new(1., 0., 1.))).is_none());
+
+        let tiny = Mat4::scaling(Vector::new(1e-7, 1e-7, 1e-7));
+        let shrunk = Entity::instance(sphere, tiny).unwrap();
+        assert!(shrunk.transformed(tiny).is_none());
+    }
+
+    #[test]
+    fn bounding_box() {
+        let cube = Arc::new(Entity::cuboid(
+            Point::new(0., 0., 0.),
+            Point::new(1., 1., 1.),
+            Material::Lambertian(Color::white().into()),
+        ));
+        let instance = Instance::new(cube, Mat4::rotation(Vector::new(0., 1., 0.), 45.0)).unwrap();
+        let b = instance.bounding_box();
+        let half = 0.5_f32.sqrt();
+        // The cube's faces are padded slightly, so compare loosely.
+        let near = |a: f32, b: f32| (a - b).abs() < 1e-3;
+
+        assert!(near(b.x.min, 0.0) && near(b.x.max, 2.0 * half));
+        assert!(near(b.z.min, -half) && near(b.z.max, half));
+        assert!(near(b.y.min, 0.0) && near(b.y.max, 1.0));
+    }
+
+    #[test]
+    fn passes_the_free_flight_through() {
+        let fog = || {
+            Entity::constant_medium(
+                Entity::sphere(
+                    Point::new(0., 0., 0.),
+                    1.0,
+                    Material::Lambertian(Color::white().into()),
+                ),
+                1.0,
+                Color::white().into(),
+            )
+        };
+        let moved =
+            Instance::new(Arc::new(fog()), Mat4::translation(Vector::new(0., 0., -5.))).unwrap();
+        let direct = fog();
+        let i = Interval::new(0.001, f32::INFINITY);
+        let mut r = Ray::new(Point::new(0., 0., 0.), Vector::new(0., 0., -1.), 0.0);
+        let mut distances = vec![];
+        for u in [0.1, 0.3, 0.5, 0.7, 0.9] {
+            r.free_flight = u;
+            let t = moved.hit(&r, &i).map(|rec| rec.t);
+            let shifted = Ray::new(Point::new(0., 0., 5.), r.direction, 0.0);
+            let expected = direct
+                .hit(
+                    &Ray {
+                        free_flight: u,
+                        ..shifted
+                    },
+                    &i,
+                )
+                .map(|rec| rec.t);
+            assert_eq!(t.is_some(), expected.is_some());
+            if let (Some(t), Some(expected)) = (t, expected) {
+                assert!((t - expected).abs() < 1e-4);
+                distances.push(t);
+            }
+        }
+        assert!(distances.windows(2).any(|w| w[0] != w[1]));
+    }
+}
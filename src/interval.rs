@@ -9,6 +9,27 @@ impl Interval {
         Self { min, max }
     }
 
+    pub fn empty() -> Self {
+        Self::new(f32::INFINITY, f32::NEG_INFINITY)
+    }
+
+    pub fn universe() -> Self {
+        Self::new(f32::NEG_INFINITY, f32::INFINITY)
+    }
+
+    pub fn enclosing(a: Self, b: Self) -> Self {
+        Self::new(a.min.min(b.min), a.max.max(b.max))
+    }
+
+    pub fn size(self) -> f32 {
+        self.max - self.min
+    }
+
+    pub fn expand(self, δ: f32) -> Self {
+        let padding = δ / 2.0;
+        Self::new(self.min - padding, self.max + padding)
+    }
+
     pub fn contains(self, x: f32) -> bool {
         self.min <= x && x <= self.max
     }
@@ -56,6 +77,29 @@ mod tests {
         assert!(!i.surrounds(2.0001));
     }
 
+    #[test]
+    fn enclosing() {
+        let a = Interval::new(1.0, 2.0);
+        let b = Interval::new(-1.0, 1.5);
+        let e = Interval::enclosing(a, b);
+
+        assert_eq!(e.min, -1.0);
+        assert_eq!(e.max, 2.0);
+
+        let e = Interval::enclosing(Interval::empty(), a);
+        assert_eq!(e.min, 1.0);
+        assert_eq!(e.max, 2.0);
+    }
+
+    #[test]
+    fn expand() {
+        let i = Interval::new(1.0, 2.0).expand(0.5);
+
+        assert_eq!(i.min, 0.75);
+        assert_eq!(i.max, 2.25);
+        assert_eq!(i.size(), 1.5);
+    }
+
     #[test]
     fn clamp() {
         let i = Interval::new(1.0, 2.0);
@@ -1,22 +1,53 @@
 #![allow(mixed_script_confusables)]
 
+pub mod aabb;
+pub mod adaptive;
+pub mod bvh;
 pub mod camera;
 pub mod color;
+pub mod dispersion;
 pub mod entity;
+pub mod exr;
+pub mod film;
+pub mod framebuffer;
+pub mod fresnel;
+pub mod hdr;
 pub mod hit_record;
 pub mod hittable;
+pub mod image;
+pub mod instance;
 pub mod interval;
+pub mod light;
 pub mod material;
+pub mod medium;
+pub mod mesh;
+pub mod microfacet;
+pub mod obj;
+pub mod perlin;
+pub mod png;
+pub mod ppm;
+pub mod progress;
+pub mod quad;
 pub mod ray;
+pub mod rng;
+pub mod sampler;
+pub mod scene;
+pub mod spectrum;
+pub mod texture;
+pub mod tonemap;
+pub mod triangle;
 pub mod vec3;
 pub mod world;
+pub mod zlib;
 
 pub mod prelude {
     pub use super::camera::Camera;
     pub use super::color::Color;
     pub use super::entity::Entity;
     pub use super::entity::Entity::Sphere;
+    pub use super::framebuffer::Framebuffer;
     pub use super::material::Material;
+    pub use super::texture::Texture;
     pub use super::vec3::{Point, Vector};
     pub use super::world::World;
 }
@@ -0,0 +1,183 @@
+use std::f32::consts::PI;
+
+use crate::{
+    entity::{hit_sphere, Entity},
+    hit_record::HitRecord,
+    interval::Interval,
+    material::Material,
+    quad::Quad,
+    ray::Ray,
+    vec3::{Point, Vector},
+};
+
+/// An emissive shape that can be sampled directly, copied out of the
+/// world's entities.
+pub enum Light {
+    Sphere(Ray, f32, Material),
+    Quad(Quad, Material),
+}
+
+impl Light {
+    /// The light for an emissive sphere or quad, searching through groups.
+    /// Anything else, including lights placed through instances, is left
+    /// to be found by scattering alone.
+    pub fn collect(entity: &Entity, lights: &mut Vec<Light>) {
+        match entity {
+            Entity::Sphere(center, radius, material) if material.is_emissive() => {
+                lights.push(Light::Sphere(*center, *radius, material.clone()))
+            }
+            Entity::Quad(quad, material) if material.is_emissive() => {
+                lights.push(Light::Quad(*quad, material.clone()))
+            }
+            Entity::Group(entities) => {
+                for e in entities {
+                    Light::collect(e, lights);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        match self {
+            Light::Sphere(center, radius, material) => {
+                hit_sphere(ray, interval, center, *radius, material)
+            }
+            Light::Quad(quad, material) => quad.hit(ray, interval, material),
+        }
+    }
+
+    /// A direction from `origin` toward a point on the light chosen by `u`,
+    /// or `None` if the light can't be sampled from there.
+    pub fn sample(&self, origin: Point, time: f32, u: (f32, f32)) -> Option<Vector> {
+        match self {
+            Light::Sphere(center, radius, _) => {
+                let to_center = center.at(time) - origin;
+                let one_minus_cos_max = sphere_cone(to_center.length_squared(), *radius)?;
+                // Uniform over the cone of directions the sphere subtends.
+                let cos = 1.0 - u.0 * one_minus_cos_max;
+                let sin = (1.0 - cos * cos).max(0.0).sqrt();
+                let φ = 2.0 * PI * u.1;
+                let w = to_center.normalize();
+                let (u, v) = w.orthonormal_basis();
+                Some(u * (sin * φ.cos()) + v * (sin * φ.sin()) + w * cos)
+            }
+            Light::Quad(quad, _) => {
+                let p = quad.q + quad.u * u.0 + quad.v * u.1;
+                Some(p - origin)
+            }
+        }
+    }
+
+    /// The solid angle density with which `sample` picks `direction` from
+    /// `origin`; zero if it misses the light.
+    pub fn pdf(&self, origin: Point, direction: Vector, time: f32) -> f32 {
+        let ray = Ray::new(origin, direction, time);
+        let Some(rec) = self.hit(&ray, &Interval::new(0.001, f32::INFINITY)) else {
+            return 0.0;
+        };
+        match self {
+            Light::Sphere(center, radius, _) => {
+                let to_center = center.at(time) - origin;
+                match sphere_cone(to_center.length_squared(), *radius) {
+                    Some(one_minus_cos_max) => 1.0 / (2.0 * PI * one_minus_cos_max),
+                    None => 0.0,
+                }
+            }
+            Light::Quad(quad, _) => {
+                let distance_squared = rec.t * rec.t * direction.length_squared();
+                let cosine = (direction.dot(quad.normal()) / direction.magnitude()).abs();
+                if cosine < 1e-6 {
+                    return 0.0;
+                }
+                distance_squared / (cosine * quad.area())
+            }
+        }
+    }
+}
+
+/// 1 - cos θmax for the cone a sphere subtends, computed without
+/// cancellation for small, distant spheres. `None` from inside the sphere.
+fn sphere_cone(distance_squared: f32, radius: f32) -> Option<f32> {
+    let sin2 = radius * radius / distance_squared;
+    if sin2 >= 1.0 {
+        return None;
+    }
+    Some(sin2 / (1.0 + (1.0 - sin2).sqrt()))
+}
+
+/// Veach's power heuristic with β = 2, weighting the strategy that
+/// produced a sample against another.
+pub fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0.0 {
+        return 0.0;
+    }
+    a / (a + b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, rng::Pcg32};
+    use rand::prelude::*;
+
+    /// Monte Carlo estimate of the integral of a light's pdf over the
+    /// sphere of directions, which should be 1.
+    fn integrate_pdf(light: &Light, origin: Point) -> f32 {
+        let n = 200_000;
+        let mut rng = Pcg32::new(1, 0);
+        let total: f32 = (0..n)
+            .map(|_| light.pdf(origin, Vector::random_normalized(&mut rng), 0.0))
+            .sum();
+        total * 4.0 * PI / n as f32
+    }
+
+    #[test]
+    fn sphere() {
+        let light = Light::Sphere(
+            Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 0.), 0.0),
+            1.0,
+            Material::DiffuseLight(Color::white()),
+        );
+        let origin = Point::new(0., 0., 0.);
+
+        assert!((integrate_pdf(&light, origin) - 1.0).abs() < 0.05);
+        let mut rng = Pcg32::new(2, 0);
+        for _ in 0..100 {
+            let d = light.sample(origin, 0.0, rng.gen()).unwrap();
+            assert!(light.pdf(origin, d, 0.0) > 0.0);
+        }
+        assert!(light
+            .sample(Point::new(0., 0., -5.5), 0.0, rng.gen())
+            .is_none());
+    }
+
+    #[test]
+    fn quad() {
+        let light = Light::Quad(
+            Quad::new(
+                Point::new(-1., 2., -1.),
+                Vector::new(2., 0., 0.),
+                Vector::new(0., 0., 2.),
+            ),
+            Material::DiffuseLight(Color::white()),
+        );
+        let origin = Point::new(0., 0., 0.);
+
+        assert!((integrate_pdf(&light, origin) - 1.0).abs() < 0.05);
+        let mut rng = Pcg32::new(2, 0);
+        for _ in 0..100 {
+            let d = light.sample(origin, 0.0, rng.gen()).unwrap();
+            assert!(light.pdf(origin, d, 0.0) > 0.0);
+        }
+        assert_eq!(light.pdf(origin, Vector::new(0., -1., 0.), 0.0), 0.0);
+    }
+
+    #[test]
+    fn power_heuristic() {
+        assert_eq!(super::power_heuristic(1.0, 1.0), 0.5);
+        assert_eq!(super::power_heuristic(3.0, 0.0), 1.0);
+        assert!((super::power_heuristic(1.0, 2.0) - 0.2).abs() < 1e-6);
+    }
+}
@@ -1,22 +1,38 @@
 #![allow(mixed_script_confusables)]
 
-use std::env;
+use std::{env, process};
 
 use rand::prelude::*;
 use rust_tracer::material::Material::*;
 use rust_tracer::prelude::*;
+use rust_tracer::rng::Pcg32;
+use rust_tracer::scene;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
+    if args.len() > 1 && args[1].ends_with(".toml") {
+        let (mut c, world) = scene::load(&args[1]).unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        });
+        c.render(&world, args.get(2).map_or("image.ppm", |s| s.as_str()));
+        return;
+    }
+
     let mut filename = "image.ppm";
     if args.len() > 1 {
         filename = &args[1];
     }
+    random_spheres(filename);
+}
+
+fn random_spheres(filename: &str) {
     let mut c = Camera::new();
     c.aspect_ratio = 16.0 / 9.0;
     c.image_width = 1200;
     c.samples_per_pixel = 500;
     c.max_depth = 50;
+    c.russian_roulette_depth = Some(3);
     c.vfov = 20.0;
     c.lookfrom = Point::new(13., 2., 3.);
     c.lookat = Point::new(0., 0., 0.);
@@ -29,21 +45,25 @@ fn main() {
     world.add(Entity::sphere(
         Point::new(0., -1000., 0.),
         1000.0,
-        Lambertian(Color::new(0.5, 0.5, 0.5)),
+        Lambertian(Color::new(0.5, 0.5, 0.5).into()),
+    ));
+    world.add(Entity::sphere(
+        Point::new(0., 1., 0.),
+        1.0,
+        Material::dielectric(1.5),
     ));
-    world.add(Entity::sphere(Point::new(0., 1., 0.), 1.0, Dielectric(1.5)));
     world.add(Entity::sphere(
         Point::new(-4., 1., 0.),
         1.0,
-        Lambertian(Color::new(0.1, 0.2, 0.4)),
+        Lambertian(Color::new(0.1, 0.2, 0.4).into()),
     ));
     world.add(Entity::sphere(
         Point::new(4., 1., 0.),
         1.0,
-        Metal(Color::new(0.7, 0.6, 0.5), 0.0),
+        Metal(Color::new(0.7, 0.6, 0.5).into(), 0.0),
     ));
 
-    let mut rng = rand::thread_rng();
+    let mut rng = Pcg32::new(c.seed, 0);
     for a in -11..11 {
         for b in -11..11 {
             let choose_mat = rng.gen::<f32>();
@@ -55,17 +75,17 @@ fn main() {
 
             if (center - Point::new(4., 0.2, 0.)).magnitude() > 0.9 {
                 if choose_mat < 0.8 {
-                    let albedo = Color::random() * Color::random();
-                    let material = Lambertian(albedo);
+                    let albedo = Color::random(&mut rng) * Color::random(&mut rng);
+                    let material = Lambertian(albedo.into());
                     let center2 = center + Point::new(0., rng.gen_range(0.0..0.5), 0.);
                     world.add(Entity::moving_sphere(center, center2, 0.2, material));
                 } else if choose_mat < 0.95 {
-                    let albedo = Color::random_in(0.5, 1.0);
+                    let albedo = Color::random_in(&mut rng, 0.5, 1.0);
                     let fuzz = rng.gen_range(0.0..0.5);
-                    let material = Metal(albedo, fuzz);
+                    let material = Metal(albedo.into(), fuzz);
                     world.add(Entity::sphere(center, 0.2, material));
                 } else {
-                    let material = Dielectric(1.5);
+                    let material = Material::dielectric(1.5);
                     world.add(Entity::sphere(center, 0.2, material));
                 };
             }
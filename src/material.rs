@@ -1,79 +1,727 @@
-use crate::{color::Color, hit_record::HitRecord, ray::Ray, vec3::Vec3};
-use rand::prelude::*;
+use std::f32::consts::PI;
 
-pub struct Scatter {
-    pub attenuation: Color,
-    pub scattered: Ray,
+use crate::{
+    color::Color,
+    dispersion::RefractionIndex,
+    fresnel,
+    hit_record::HitRecord,
+    microfacet::TrowbridgeReitz,
+    sampler::{cosine_hemisphere, uniform_sphere},
+    texture::Texture,
+    vec3::Vector,
+};
+
+/// A direction chosen by `Material::sample`.
+pub struct BsdfSample {
+    /// Unit direction the light scatters into.
+    pub direction: Vector,
+    /// The BSDF times the cosine term, divided by `pdf`: what the light
+    /// arriving from `direction` is multiplied by.
+    pub weight: Color,
+    /// Solid angle density of `direction`, or for a delta lobe the
+    /// probability of having picked that lobe.
+    pub pdf: f32,
+    /// Whether the direction came from a perfectly specular lobe, which
+    /// `eval` and `pdf` report as zero.
+    pub is_delta: bool,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum Material {
-    Lambertian(Color),
-    Metal(Color, f32),
-    Dielectric(f32),
+    Lambertian(Texture),
+    /// A reflective surface whose mirror direction is perturbed by a
+    /// random offset within the given fuzz radius.
+    Metal(Texture, f32),
+    /// Glass with the given refractive index, and absorption coefficient
+    /// per unit distance inside it; black for clear glass.
+    Dielectric(RefractionIndex, Color),
+    /// Frosted glass: a dielectric whose surface has a GGX distribution of
+    /// normals (Walter et al. 2007).
+    RoughDielectric(RefractionIndex, TrowbridgeReitz, Color),
+    /// A microfacet metal with the given per-channel complex refractive
+    /// index, η and k, and GGX distribution of normals.
+    Conductor(Color, Color, TrowbridgeReitz),
+    DiffuseLight(Color),
+    /// Scatters uniformly in all directions; the phase function for
+    /// participating media.
+    Isotropic(Texture),
+}
+
+impl Material {
+    /// A conductor with roughness in [0, 1], zero being a perfect mirror.
+    pub fn conductor(eta: Color, k: Color, roughness: f32) -> Self {
+        Material::Conductor(eta, k, TrowbridgeReitz::from_roughness(roughness))
+    }
+
+    /// Clear glass.
+    pub fn dielectric(refraction_index: f32) -> Self {
+        Material::Dielectric(refraction_index.into(), Color::black())
+    }
+
+    /// Clear glass with roughness in [0, 1], zero being smooth.
+    pub fn rough_dielectric(refraction_index: f32, roughness: f32) -> Self {
+        Material::RoughDielectric(
+            refraction_index.into(),
+            TrowbridgeReitz::from_roughness(roughness),
+            Color::black(),
+        )
+    }
+
+    pub fn gold(roughness: f32) -> Self {
+        Self::conductor(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.386, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f32) -> Self {
+        Self::conductor(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f32) -> Self {
+        Self::conductor(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+
+    pub fn aluminum(roughness: f32) -> Self {
+        Self::conductor(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
 }
 
+// Directions follow the usual convention: `wo` points back toward where
+// the light is going (the viewer), `wi` toward where it comes from, and
+// both are unit vectors. The hit record's normal faces `wo`. When
+// rendering spectrally, `wavelength` is the one in nm that dispersive
+// materials refract.
 impl Material {
-    pub fn scatter(self, ray: &Ray, hr: &HitRecord) -> Option<Scatter> {
+    /// The BSDF for light arriving from `wi` and leaving along `wo`, times
+    /// the cosine of `wi` with the normal.
+    pub fn eval(&self, hr: &HitRecord, wo: Vector, wi: Vector, wavelength: Option<f32>) -> Color {
+        match self {
+            Material::Lambertian(albedo) => {
+                albedo.value(hr.u, hr.v, hr.p) * (hr.normal.dot(wi).max(0.0) / PI)
+            }
+            Material::Metal(albedo, fuzz) if *fuzz > 0.0 => {
+                // The lobe is defined so that its BSDF times the cosine is
+                // the albedo times the sampling density.
+                albedo.value(hr.u, hr.v, hr.p) * fuzzy_reflection_pdf(hr, wo, wi, *fuzz)
+            }
+            Material::Conductor(eta, k, distribution) if !distribution.is_smooth() => {
+                let frame = Frame::new(hr.normal);
+                let (wo, wi) = (frame.to_local(wo), frame.to_local(wi));
+                if wo.z <= 0.0 || wi.z <= 0.0 {
+                    return Color::black();
+                }
+                let wm = wo + wi;
+                if wm.is_near_zero() {
+                    return Color::black();
+                }
+                let wm = wm.normalize();
+                fresnel::conductor(wo.dot(wm), *eta, *k)
+                    * (distribution.d(wm) * distribution.g(wo, wi) / (4.0 * wo.z))
+            }
+            Material::RoughDielectric(refraction_index, distribution, _)
+                if !distribution.is_smooth() =>
+            {
+                let frame = Frame::new(hr.normal);
+                let eta = relative_eta(*refraction_index, hr, wavelength);
+                let (wo, wi) = (frame.to_local(wo), frame.to_local(wi));
+                let Some(facet) = Facet::between(wo, wi, eta) else {
+                    return Color::black();
+                };
+                Color::white() * (rough_dielectric_f(*distribution, wo, wi, &facet) * wi.z.abs())
+            }
+            Material::Isotropic(albedo) => albedo.value(hr.u, hr.v, hr.p) / (4.0 * PI),
+            _ => Color::black(),
+        }
+    }
+
+    /// Picks a direction for `wi` in proportion to (roughly) the BSDF, or
+    /// `None` if the light is absorbed. `uc` chooses between lobes and `u`
+    /// the direction within one; both are uniform in [0, 1).
+    pub fn sample(
+        &self,
+        hr: &HitRecord,
+        wo: Vector,
+        uc: f32,
+        u: (f32, f32),
+        wavelength: Option<f32>,
+    ) -> Option<BsdfSample> {
         match self {
-            Material::Lambertian(albedo) => scatter_lambertian(albedo, ray, hr),
-            Material::Metal(albedo, fuzz) => scatter_metal(albedo, fuzz, ray, hr),
-            Material::Dielectric(refraction_index) => scatter_dielectric(refraction_index, ray, hr),
+            Material::Lambertian(albedo) => {
+                let (s, t) = hr.normal.orthonormal_basis();
+                let local = cosine_hemisphere(u);
+                let direction = (s * local.x + t * local.y + hr.normal * local.z).normalize();
+                Some(BsdfSample {
+                    direction,
+                    weight: albedo.value(hr.u, hr.v, hr.p),
+                    pdf: hr.normal.dot(direction).max(0.0) / PI,
+                    is_delta: false,
+                })
+            }
+            Material::Metal(albedo, fuzz) => {
+                let reflected = (-wo).reflect(hr.normal);
+                let direction = (reflected + uniform_sphere(u) * *fuzz).normalize();
+                if hr.normal.dot(direction) <= 0.0 {
+                    return None;
+                }
+                let is_delta = *fuzz <= 0.0;
+                let pdf = if is_delta {
+                    1.0
+                } else {
+                    fuzzy_reflection_pdf(hr, wo, direction, *fuzz)
+                };
+                // Rounding can put a direction on the lobe's silhouette
+                // just outside it.
+                if pdf <= 0.0 || !pdf.is_finite() {
+                    return None;
+                }
+                Some(BsdfSample {
+                    direction,
+                    weight: albedo.value(hr.u, hr.v, hr.p),
+                    pdf,
+                    is_delta,
+                })
+            }
+            Material::Dielectric(refraction_index, _) => {
+                Some(sample_dielectric(*refraction_index, hr, wo, uc, wavelength))
+            }
+            Material::RoughDielectric(refraction_index, distribution, _) => {
+                if distribution.is_smooth() {
+                    return Some(sample_dielectric(*refraction_index, hr, wo, uc, wavelength));
+                }
+                sample_rough_dielectric(*refraction_index, *distribution, hr, wo, uc, u, wavelength)
+            }
+            Material::Conductor(eta, k, distribution) => {
+                let frame = Frame::new(hr.normal);
+                let wo = frame.to_local(wo);
+                if wo.z <= 0.0 {
+                    return None;
+                }
+                if distribution.is_smooth() {
+                    return Some(BsdfSample {
+                        direction: frame.to_world(Vector::new(-wo.x, -wo.y, wo.z)),
+                        weight: fresnel::conductor(wo.z, *eta, *k),
+                        pdf: 1.0,
+                        is_delta: true,
+                    });
+                }
+                let wm = distribution.sample_visible_normal(wo, u);
+                let wi = wm * (2.0 * wo.dot(wm)) - wo;
+                if wi.z <= 0.0 {
+                    return None;
+                }
+                let pdf = distribution.visible_d(wo, wm) / (4.0 * wo.dot(wm));
+                if pdf <= 0.0 || !pdf.is_finite() {
+                    return None;
+                }
+                // eval / pdf, with D and most of the geometry cancelled.
+                Some(BsdfSample {
+                    direction: frame.to_world(wi).normalize(),
+                    weight: fresnel::conductor(wo.dot(wm), *eta, *k)
+                        * (distribution.g(wo, wi) / distribution.g1(wo)),
+                    pdf,
+                    is_delta: false,
+                })
+            }
+            Material::DiffuseLight(_) => None,
+            Material::Isotropic(albedo) => Some(BsdfSample {
+                direction: uniform_sphere(u),
+                weight: albedo.value(hr.u, hr.v, hr.p),
+                pdf: 1.0 / (4.0 * PI),
+                is_delta: false,
+            }),
         }
     }
+
+    /// Density with which `sample` picks `wi`, leaving out delta lobes.
+    pub fn pdf(&self, hr: &HitRecord, wo: Vector, wi: Vector, wavelength: Option<f32>) -> f32 {
+        match self {
+            Material::Lambertian(_) => hr.normal.dot(wi).max(0.0) / PI,
+            Material::Metal(_, fuzz) if *fuzz > 0.0 => fuzzy_reflection_pdf(hr, wo, wi, *fuzz),
+            Material::Conductor(_, _, distribution) if !distribution.is_smooth() => {
+                let frame = Frame::new(hr.normal);
+                let (wo, wi) = (frame.to_local(wo), frame.to_local(wi));
+                if wo.z <= 0.0 || wi.z <= 0.0 {
+                    return 0.0;
+                }
+                let wm = wo + wi;
+                if wm.is_near_zero() {
+                    return 0.0;
+                }
+                let wm = wm.normalize();
+                distribution.visible_d(wo, wm) / (4.0 * wo.dot(wm))
+            }
+            Material::RoughDielectric(refraction_index, distribution, _)
+                if !distribution.is_smooth() =>
+            {
+                let frame = Frame::new(hr.normal);
+                let eta = relative_eta(*refraction_index, hr, wavelength);
+                let (wo, wi) = (frame.to_local(wo), frame.to_local(wi));
+                match Facet::between(wo, wi, eta) {
+                    Some(facet) => rough_dielectric_pdf(*distribution, wo, wi, &facet),
+                    None => 0.0,
+                }
+            }
+            Material::Isotropic(_) => 1.0 / (4.0 * PI),
+            _ => 0.0,
+        }
+    }
+
+    /// For a dielectric, the absorption coefficient of the medium behind
+    /// its surface, which a path refracting through it enters or leaves.
+    pub fn interior(&self) -> Option<Color> {
+        match self {
+            Material::Dielectric(_, absorption) | Material::RoughDielectric(_, _, absorption) => {
+                Some(*absorption)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether light leaving in a given direction depends on its
+    /// wavelength, which spectral rendering can only follow one of.
+    pub fn is_dispersive(&self) -> bool {
+        match self {
+            Material::Dielectric(refraction_index, _)
+            | Material::RoughDielectric(refraction_index, _, _) => refraction_index.is_dispersive(),
+            _ => false,
+        }
+    }
+
+    /// Whether every direction `sample` can return comes from a delta
+    /// lobe, so there's nothing for light sampling to find.
+    pub fn is_delta(&self) -> bool {
+        match self {
+            Material::Metal(_, fuzz) => *fuzz <= 0.0,
+            Material::Dielectric(..) => true,
+            Material::RoughDielectric(_, distribution, _) => distribution.is_smooth(),
+            Material::Conductor(_, _, distribution) => distribution.is_smooth(),
+            _ => false,
+        }
+    }
+
+    pub fn is_emissive(&self) -> bool {
+        matches!(self, Material::DiffuseLight(_))
+    }
+
+    /// Radiance emitted from the surface at the hit point.
+    pub fn emitted(&self, _hr: &HitRecord) -> Color {
+        match self {
+            Material::DiffuseLight(radiance) => *radiance,
+            _ => Color::black(),
+        }
+    }
+}
+
+/// An orthonormal basis about the shading normal, for the BSDFs that are
+/// easiest to write with the normal as +z.
+struct Frame {
+    s: Vector,
+    t: Vector,
+    n: Vector,
 }
 
-fn scatter_lambertian(attenuation: Color, ray: &Ray, hr: &HitRecord) -> Option<Scatter> {
-    let mut scatter_direction = hr.normal + Vec3::random_normalized();
-    if scatter_direction.is_near_zero() {
-        scatter_direction = hr.normal;
+impl Frame {
+    fn new(n: Vector) -> Self {
+        let (s, t) = n.orthonormal_basis();
+        Self { s, t, n }
+    }
+
+    fn to_local(&self, v: Vector) -> Vector {
+        Vector::new(v.dot(self.s), v.dot(self.t), v.dot(self.n))
+    }
+
+    fn to_world(&self, v: Vector) -> Vector {
+        self.s * v.x + self.t * v.y + self.n * v.z
     }
-    let scattered = Ray::new(hr.p, scatter_direction, ray.time);
-    Some(Scatter {
-        attenuation,
-        scattered,
-    })
 }
 
-fn scatter_metal(attenuation: Color, fuzz: f32, ray: &Ray, hr: &HitRecord) -> Option<Scatter> {
-    let mut reflected = ray.direction.reflect(hr.normal);
-    reflected = reflected.normalize() + Vec3::random_normalized() * fuzz;
-    let scattered = Ray::new(hr.p, reflected, ray.time);
-    Some(Scatter {
-        attenuation,
-        scattered,
-    })
+/// Density of `wi` when it's the mirror direction plus a uniform point in
+/// a sphere of radius `fuzz`, normalized. Each place the ray along `wi`
+/// crosses that sphere contributes t² / (4π fuzz √disc). Directions below
+/// the surface are absorbed, so have no density.
+fn fuzzy_reflection_pdf(hr: &HitRecord, wo: Vector, wi: Vector, fuzz: f32) -> f32 {
+    if hr.normal.dot(wi) <= 0.0 {
+        return 0.0;
+    }
+    let reflected = (-wo).reflect(hr.normal);
+    let b = wi.dot(reflected);
+    let discriminant = b * b - 1.0 + fuzz * fuzz;
+    if discriminant <= 0.0 {
+        return 0.0;
+    }
+    let root = discriminant.sqrt();
+    [b - root, b + root]
+        .iter()
+        .filter(|&&t| t > 0.0)
+        .map(|t| t * t / (4.0 * PI * fuzz * root))
+        .sum()
+}
+
+/// The fraction of light left after crossing `distance` of a medium with
+/// the given absorption coefficient (Beer-Lambert).
+pub fn transmittance(absorption: Color, distance: f32) -> Color {
+    Color::new(
+        (-absorption.x * distance).exp(),
+        (-absorption.y * distance).exp(),
+        (-absorption.z * distance).exp(),
+    )
 }
 
-fn scatter_dielectric(refraction_index: f32, ray: &Ray, hr: &HitRecord) -> Option<Scatter> {
-    let mut rng = rand::thread_rng();
-    let attenuation = Color::white();
-    let ri = if hr.front_face {
-        1.0 / refraction_index
+/// The absorption coefficient of a medium that lets through `transmittance`
+/// of the light crossing `depth` of it.
+pub fn absorption_coefficient(transmittance: Color, depth: f32) -> Color {
+    let coefficient = |t: f32| -t.max(1e-6).ln() / depth;
+    Color::new(
+        coefficient(transmittance.x),
+        coefficient(transmittance.y),
+        coefficient(transmittance.z),
+    )
+}
+
+/// The index of refraction beyond the surface over the one on the side
+/// the hit record's normal faces.
+fn relative_eta(refraction_index: RefractionIndex, hr: &HitRecord, wavelength: Option<f32>) -> f32 {
+    let n = refraction_index.at(wavelength);
+    if hr.front_face {
+        n
     } else {
-        refraction_index
+        1.0 / n
+    }
+}
+
+fn sample_dielectric(
+    refraction_index: RefractionIndex,
+    hr: &HitRecord,
+    wo: Vector,
+    uc: f32,
+    wavelength: Option<f32>,
+) -> BsdfSample {
+    let eta = relative_eta(refraction_index, hr, wavelength);
+    let unit_direction = -wo;
+    let cosθ = wo.dot(hr.normal).min(1.0);
+    let reflect_probability = fresnel::dielectric(cosθ, eta);
+
+    // Choosing between the lobes in proportion to the Fresnel term leaves
+    // a weight of one either way.
+    let (direction, pdf) = if uc < reflect_probability {
+        (unit_direction.reflect(hr.normal), reflect_probability)
+    } else {
+        (
+            unit_direction.refract(hr.normal, 1.0 / eta),
+            1.0 - reflect_probability,
+        )
     };
-    let unit_direction = ray.direction.normalize();
-    let cosθ = -unit_direction.dot(hr.normal).min(1.0);
-    let sinθ = (1.0 - cosθ * cosθ).sqrt();
 
-    let cannot_refract = ri * sinθ > 1.0;
+    BsdfSample {
+        direction: direction.normalize(),
+        weight: Color::white(),
+        pdf,
+        is_delta: true,
+    }
+}
+
+/// The microfacet that scatters local direction `wo` into `wi` across a
+/// boundary with relative index `eta`: the generalized half vector.
+struct Facet {
+    wm: Vector,
+    reflect: bool,
+    fresnel: f32,
+    /// (wi·wm + wo·wm / η)², the Jacobian's denominator for transmission.
+    denominator: f32,
+}
 
-    let direction = if cannot_refract || (reflectance(cosθ, ri) > rng.gen::<f32>()) {
-        unit_direction.reflect(hr.normal)
+impl Facet {
+    fn between(wo: Vector, wi: Vector, eta: f32) -> Option<Facet> {
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return None;
+        }
+        let reflect = wi.z > 0.0;
+        let etap = if reflect { 1.0 } else { eta };
+        let wm = wi * etap + wo;
+        if wm.is_near_zero() {
+            return None;
+        }
+        let mut wm = wm.normalize();
+        if wm.z < 0.0 {
+            wm = -wm;
+        }
+        // Microfacets facing away from either direction can't connect them.
+        if wm.dot(wi) * wi.z < 0.0 || wm.dot(wo) < 0.0 {
+            return None;
+        }
+        let denominator = (wi.dot(wm) + wo.dot(wm) / etap).powi(2);
+        Some(Facet {
+            wm,
+            reflect,
+            fresnel: fresnel::dielectric(wo.dot(wm), eta),
+            denominator,
+        })
+    }
+}
+
+fn rough_dielectric_f(distribution: TrowbridgeReitz, wo: Vector, wi: Vector, facet: &Facet) -> f32 {
+    let dg = distribution.d(facet.wm) * distribution.g(wo, wi);
+    if facet.reflect {
+        dg * facet.fresnel / (4.0 * wo.z * wi.z)
     } else {
-        unit_direction.refract(hr.normal, ri)
+        let jacobian = wi.dot(facet.wm) * wo.dot(facet.wm) / (wo.z * wi.z * facet.denominator);
+        dg * (1.0 - facet.fresnel) * jacobian.abs()
+    }
+}
+
+fn rough_dielectric_pdf(
+    distribution: TrowbridgeReitz,
+    wo: Vector,
+    wi: Vector,
+    facet: &Facet,
+) -> f32 {
+    let visible = distribution.visible_d(wo, facet.wm);
+    if facet.reflect {
+        visible / (4.0 * wo.dot(facet.wm)) * facet.fresnel
+    } else {
+        visible * wi.dot(facet.wm).abs() / facet.denominator * (1.0 - facet.fresnel)
+    }
+}
+
+/// Picks a visible microfacet, then reflects or refracts through it in
+/// proportion to its Fresnel reflectance.
+fn sample_rough_dielectric(
+    refraction_index: RefractionIndex,
+    distribution: TrowbridgeReitz,
+    hr: &HitRecord,
+    wo: Vector,
+    uc: f32,
+    u: (f32, f32),
+    wavelength: Option<f32>,
+) -> Option<BsdfSample> {
+    let frame = Frame::new(hr.normal);
+    let eta = relative_eta(refraction_index, hr, wavelength);
+    let wo = frame.to_local(wo);
+    if wo.z <= 0.0 {
+        return None;
+    }
+    let wm = distribution.sample_visible_normal(wo, u);
+    let fresnel = fresnel::dielectric(wo.dot(wm), eta);
+
+    let reflected = uc < fresnel;
+    let wi = if reflected {
+        wm * (2.0 * wo.dot(wm)) - wo
+    } else {
+        let cos_i = wo.dot(wm);
+        let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+        if sin2_t >= 1.0 {
+            return None;
+        }
+        let cos_t = (1.0 - sin2_t).sqrt();
+        -wo / eta + wm * (cos_i / eta - cos_t)
     };
+    if (wi.z > 0.0) != reflected || wi.z == 0.0 {
+        return None;
+    }
 
-    let scattered = Ray::new(hr.p, direction, ray.time);
-    Some(Scatter {
-        attenuation,
-        scattered,
+    // The sampled facet is the one `between` recovers, up to rounding.
+    let facet = Facet::between(wo, wi, eta)?;
+    let pdf = rough_dielectric_pdf(distribution, wo, wi, &facet);
+    if pdf <= 0.0 || !pdf.is_finite() {
+        return None;
+    }
+    let f = rough_dielectric_f(distribution, wo, wi, &facet);
+    Some(BsdfSample {
+        direction: frame.to_world(wi).normalize(),
+        weight: Color::white() * (f * wi.z.abs() / pdf),
+        pdf,
+        is_delta: false,
     })
 }
 
-fn reflectance(cosine: f32, refraction_index: f32) -> f32 {
-    let mut r0 = (1.0 - refraction_index) / (1.0 + refraction_index);
-    r0 *= r0;
-    r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ray::Ray, rng::Pcg32, vec3::Point};
+    use rand::prelude::*;
+
+    fn hit_record(material: &Material) -> HitRecord<'_> {
+        let ray = Ray::new(Point::new(0., 1., 1.), Vector::new(0., -1., -1.), 0.0);
+        HitRecord::new(
+            Point::new(0., 0., 0.),
+            Vector::new(0., 1., 0.),
+            1.0,
+            (0.5, 0.5),
+            &ray,
+            material,
+        )
+    }
+
+    /// Checks that sampled weights agree with eval / pdf, and that the pdf
+    /// integrated over a cone around `axis` matches the fraction of
+    /// samples landing in it.
+    fn check_consistent(material: &Material, axis: Vector, angle: f32) {
+        let hr = hit_record(material);
+        let wo = Vector::new(0., 1., 1.).normalize();
+        let cos_max = angle.cos();
+        let n = 100_000;
+        let mut rng = Pcg32::new(1, 0);
+
+        let mut inside = 0;
+        for _ in 0..n {
+            if let Some(s) = material.sample(&hr, wo, rng.gen(), rng.gen(), None) {
+                let pdf = material.pdf(&hr, wo, s.direction, None);
+                assert!((pdf - s.pdf).abs() <= 1e-3 * pdf.max(1.0));
+                let expected = material.eval(&hr, wo, s.direction, None) / s.pdf;
+                assert!((expected - s.weight).magnitude() < 1e-3);
+                if s.direction.dot(axis) >= cos_max {
+                    inside += 1;
+                }
+            }
+        }
+
+        // Uniform directions in the cone.
+        let (u, v) = axis.orthonormal_basis();
+        let total: f32 = (0..n)
+            .map(|_| {
+                let cos = 1.0 - rng.gen::<f32>() * (1.0 - cos_max);
+                let sin = (1.0 - cos * cos).sqrt();
+                let φ = 2.0 * PI * rng.gen::<f32>();
+                let wi = u * (sin * φ.cos()) + v * (sin * φ.sin()) + axis * cos;
+                material.pdf(&hr, wo, wi, None)
+            })
+            .sum();
+        let integral = total * 2.0 * PI * (1.0 - cos_max) / n as f32;
+        let fraction = inside as f32 / n as f32;
+        assert!((integral - fraction).abs() < 0.02 + 0.05 * fraction);
+    }
+
+    #[test]
+    fn lambertian() {
+        let m = Material::Lambertian(Color::new(0.5, 0.5, 0.5).into());
+        check_consistent(&m, Vector::new(0., 1., 0.), 0.5);
+    }
+
+    #[test]
+    fn fuzzy_metal() {
+        let mirror = Vector::new(0., 1., -1.).normalize();
+        let m = Material::Metal(Color::new(0.8, 0.6, 0.2).into(), 0.3);
+        check_consistent(&m, mirror, 0.2);
+        check_consistent(&Material::Metal(Color::white().into(), 1.0), mirror, 0.5);
+    }
+
+    #[test]
+    fn conductor() {
+        let mirror = Vector::new(0., 1., -1.).normalize();
+        check_consistent(&Material::gold(0.3), mirror, 0.2);
+        check_consistent(&Material::copper(0.8), mirror, 0.6);
+
+        // A near-perfect reflector loses little energy at low roughness,
+        // more as it gets rougher (single scattering only), and never gains
+        // any.
+        let wo = Vector::new(0., 1., 1.).normalize();
+        let mut rng = Pcg32::new(3, 0);
+        for (roughness, least) in [(0.2, 0.95), (0.6, 0.75), (1.0, 0.3)] {
+            let m = Material::conductor(Color::black(), Color::new(1e3, 1e3, 1e3), roughness);
+            let hr = hit_record(&m);
+            let n = 20_000;
+            let total: f32 = (0..n)
+                .filter_map(|_| m.sample(&hr, wo, rng.gen(), rng.gen(), None))
+                .map(|s| s.weight.x)
+                .sum();
+            let albedo = total / n as f32;
+            assert!(albedo <= 1.0 && albedo > least, "{}: {}", roughness, albedo);
+        }
+    }
+
+    #[test]
+    fn rough_dielectric() {
+        let mirror = Vector::new(0., 1., -1.).normalize();
+        // Refracted into glass from 45°.
+        let refracted = Vector::new(0., -0.882, -0.471).normalize();
+        check_consistent(&Material::rough_dielectric(1.5, 0.3), mirror, 0.3);
+        check_consistent(&Material::rough_dielectric(1.5, 0.3), refracted, 0.3);
+        check_consistent(&Material::rough_dielectric(1.33, 0.7), refracted, 0.8);
+
+        // Nothing is absorbed, so apart from what single scattering loses,
+        // all the light is reflected or transmitted.
+        let wo = Vector::new(0., 1., 1.).normalize();
+        let mut rng = Pcg32::new(4, 0);
+        for (roughness, least) in [(0.2, 0.98), (0.6, 0.9)] {
+            let m = Material::rough_dielectric(1.5, roughness);
+            let hr = hit_record(&m);
+            let n = 20_000;
+            let total: f32 = (0..n)
+                .filter_map(|_| m.sample(&hr, wo, rng.gen(), rng.gen(), None))
+                .map(|s| s.weight.x)
+                .sum();
+            let albedo = total / n as f32;
+            assert!(albedo <= 1.0 && albedo > least, "{}: {}", roughness, albedo);
+        }
+    }
+
+    #[test]
+    fn isotropic() {
+        let m = Material::Isotropic(Color::white().into());
+        check_consistent(&m, Vector::new(1., 0., 0.), 1.0);
+    }
+
+    #[test]
+    fn diffuse_light() {
+        let radiance = Color::new(4., 2., 1.);
+        let m = Material::DiffuseLight(radiance);
+        let hr = hit_record(&m);
+        assert!(m.is_emissive() && !m.is_delta());
+        assert_eq!(m.emitted(&hr), radiance);
+        let wo = Vector::new(0., 1., 1.).normalize();
+        assert!(m.sample(&hr, wo, 0.5, (0.5, 0.5), None).is_none());
+        assert_eq!(
+            m.eval(&hr, wo, Vector::new(0., 1., 0.), None),
+            Color::black()
+        );
+        assert!(!Material::Lambertian(Color::white().into()).is_emissive());
+    }
+
+    #[test]
+    fn specular() {
+        let metal = Material::Metal(Color::white().into(), 0.0);
+        let hr = hit_record(&metal);
+        let wo = Vector::new(0., 1., 1.).normalize();
+        let mut rng = Pcg32::new(1, 0);
+        let s = metal.sample(&hr, wo, rng.gen(), rng.gen(), None).unwrap();
+
+        assert!(metal.is_delta() && s.is_delta);
+        assert!((s.direction - Vector::new(0., 1., -1.).normalize()).magnitude() < 1e-6);
+        assert_eq!(metal.eval(&hr, wo, s.direction, None), Color::black());
+
+        let glass = Material::dielectric(1.5);
+        let hr = hit_record(&glass);
+        for _ in 0..100 {
+            let s = glass.sample(&hr, wo, rng.gen(), rng.gen(), None).unwrap();
+            assert!(s.is_delta && s.weight == Color::white());
+            assert!((s.direction.magnitude() - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn dispersion_follows_the_wavelength() {
+        let prism = Material::Dielectric(RefractionIndex::bk7(), Color::black());
+        let hr = hit_record(&prism);
+        let wo = Vector::new(0., 1., 1.).normalize();
+        // Past any Fresnel reflectance, so both refract.
+        let refract = |wavelength| prism.sample(&hr, wo, 0.99, (0.5, 0.5), Some(wavelength));
+        let (blue, red) = (refract(450.0).unwrap(), refract(650.0).unwrap());
+
+        // Blue bends further toward the inward normal.
+        assert!(blue.direction.y < red.direction.y);
+    }
 }
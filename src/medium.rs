@@ -0,0 +1,166 @@
+use rand::prelude::*;
+
+use crate::{
+    aabb::Aabb, entity::Entity, hit_record::HitRecord, hittable::Hittable, interval::Interval,
+    material::Material, ray::Ray, rng::Pcg32, vec3::Vector,
+};
+
+/// A volume of uniform density filling a closed, convex boundary. Rays
+/// passing through scatter at an exponentially distributed distance.
+pub struct ConstantMedium {
+    pub boundary: Box<Entity>,
+    neg_inv_density: f32,
+    phase_function: Material,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Entity, density: f32, phase_function: Material) -> Self {
+        Self {
+            boundary: Box::new(boundary),
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        }
+    }
+
+    pub fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        // Find where the ray's line enters and leaves the boundary, then
+        // clip that span to the interval being searched.
+        let enter = self.boundary.hit(ray, &Interval::universe())?;
+        let exit = self
+            .boundary
+            .hit(ray, &Interval::new(enter.t + 0.0001, f32::INFINITY))?;
+
+        let t_enter = enter.t.max(interval.min).max(0.0);
+        let t_exit = exit.t.min(interval.max);
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        let ray_length = ray.direction.magnitude();
+        let distance_inside = (t_exit - t_enter) * ray_length;
+        // Each medium the ray crosses needs its own draw, or it would get
+        // through all of them as often as through the thinnest. Keying the
+        // ray's value on where this boundary lies along it gives one, the
+        // same however the scene is traversed.
+        let mut rng = Pcg32::hashed(&[
+            ray.free_flight.to_bits() as u64,
+            enter.t.to_bits() as u64 | (exit.t.to_bits() as u64) << 32,
+        ]);
+        let hit_distance = self.neg_inv_density * (1.0 - rng.gen::<f32>()).ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = t_enter + hit_distance / ray_length;
+        // Normal and facing are meaningless inside a volume; the isotropic
+        // phase function ignores both.
+        let mut rec = HitRecord::new(
+            ray.at(t),
+            Vector::new(1., 0., 0.),
+            t,
+            (0.0, 0.0),
+            ray,
+            &self.phase_function,
+        );
+        rec.normal = Vector::new(1., 0., 0.);
+        rec.front_face = true;
+        Some(rec)
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, vec3::Point, world::World};
+
+    fn fog(density: f32) -> ConstantMedium {
+        let boundary = Entity::sphere(
+            Point::new(0., 0., 0.),
+            1.0,
+            Material::Lambertian(Color::white().into()),
+        );
+        ConstantMedium::new(
+            boundary,
+            density,
+            Material::Isotropic(Color::white().into()),
+        )
+    }
+
+    #[test]
+    fn dense_medium_scatters_at_the_boundary() {
+        let medium = fog(1e6);
+        let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.), 0.0);
+        let rec = medium
+            .hit(&r, &Interval::new(0.001, f32::INFINITY))
+            .unwrap();
+
+        assert!((rec.t - 4.0).abs() < 1e-3);
+        assert!(matches!(rec.material, Material::Isotropic(_)));
+    }
+
+    #[test]
+    fn respects_interval() {
+        let medium = fog(1e6);
+        let r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.), 0.0);
+
+        assert!(medium.hit(&r, &Interval::new(0.001, 3.5)).is_none());
+
+        // Starting inside the volume scatters right away.
+        let rec = medium.hit(&r, &Interval::new(5.0, f32::INFINITY)).unwrap();
+        assert!((rec.t - 5.0).abs() < 1e-3);
+    }
+
+    /// The fraction of `n` rays down the z axis, spread evenly over
+    /// `free_flight`, that `hit` lets through.
+    fn transmitted(hit: impl Fn(&Ray) -> bool, n: u32) -> f32 {
+        let passed = (0..n)
+            .filter(|i| {
+                let mut r = Ray::new(Point::new(0., 0., -5.), Vector::new(0., 0., 1.), 0.0);
+                r.free_flight = (*i as f32 + 0.5) / n as f32;
+                !hit(&r)
+            })
+            .count();
+        passed as f32 / n as f32
+    }
+
+    #[test]
+    fn transmits_exponentially() {
+        let medium = fog(1.0);
+        let i = Interval::new(0.001, f32::INFINITY);
+        let t = transmitted(|r| medium.hit(r, &i).is_some(), 10_000);
+        assert!((t - (-2.0f32).exp()).abs() < 0.01, "{}", t);
+    }
+
+    #[test]
+    fn media_in_series_each_draw_their_own_distance() {
+        let mut world = World::new();
+        for z in [0.0, 3.0] {
+            world.add(Entity::constant_medium(
+                Entity::sphere(
+                    Point::new(0., 0., z),
+                    1.0,
+                    Material::Lambertian(Color::white().into()),
+                ),
+                1.0,
+                Color::white().into(),
+            ));
+        }
+        let i = Interval::new(0.001, f32::INFINITY);
+        let t = transmitted(|r| world.hit(r, &i).is_some(), 20_000);
+        assert!((t - (-4.0f32).exp()).abs() < 0.005, "{}", t);
+    }
+
+    #[test]
+    fn misses_outside_the_boundary() {
+        let medium = fog(1e6);
+        let r = Ray::new(Point::new(0., 2., -5.), Vector::new(0., 0., 1.), 0.0);
+
+        assert!(medium
+            .hit(&r, &Interval::new(0.001, f32::INFINITY))
+            .is_none());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{
+    aabb::Aabb,
+    bvh::Bvh,
+    hit_record::HitRecord,
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    triangle::Triangle,
+    vec3::{Point, Vector},
+};
+
+/// A triangle of a `Mesh`, stored as indices into the mesh's shared
+/// vertex attribute arrays.
+#[derive(Clone, Copy)]
+pub struct Face {
+    pub positions: [usize; 3],
+    pub normals: Option<[usize; 3]>,
+    pub uvs: Option<[usize; 3]>,
+    pub material: usize,
+}
+
+/// A triangle mesh with shared vertices and its own BVH over the faces.
+pub struct Mesh {
+    pub positions: Vec<Point>,
+    pub normals: Vec<Vector>,
+    pub uvs: Vec<(f32, f32)>,
+    pub faces: Vec<Face>,
+    pub materials: Vec<Material>,
+    bvh: Bvh,
+}
+
+impl Mesh {
+    /// Every index in `faces` must be in bounds for the matching attribute
+    /// array and for `materials`.
+    pub fn new(
+        positions: Vec<Point>,
+        normals: Vec<Vector>,
+        uvs: Vec<(f32, f32)>,
+        faces: Vec<Face>,
+        materials: Vec<Material>,
+    ) -> Self {
+        let mut mesh = Self {
+            positions,
+            normals,
+            uvs,
+            faces,
+            materials,
+            bvh: Bvh::default(),
+        };
+        let boxes: Vec<Aabb> = (0..mesh.faces.len())
+            .map(|i| mesh.triangle(i).bounding_box())
+            .collect();
+        mesh.bvh = Bvh::new(&boxes);
+        mesh
+    }
+
+    pub fn triangle(&self, face: usize) -> Triangle {
+        let f = &self.faces[face];
+        Triangle {
+            vertices: f.positions.map(|i| self.positions[i]),
+            normals: f.normals.map(|n| n.map(|i| self.normals[i])),
+            uvs: f.uvs.map(|uv| uv.map(|i| self.uvs[i])),
+        }
+    }
+
+    pub fn hit(&self, ray: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        self.bvh.hit(ray, interval, |i, r, interval| {
+            let material = &self.materials[self.faces[i].material];
+            self.triangle(i).hit(r, interval, material)
+        })
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+}
@@ -0,0 +1,133 @@
+use std::f32::consts::PI;
+
+use crate::{sampler::concentric_disk, vec3::Vector};
+
+/// The GGX (Trowbridge-Reitz) distribution of microfacet normals with
+/// Smith masking-shadowing. Directions are in the local shading frame,
+/// where the surface normal is +z.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrowbridgeReitz {
+    pub alpha: f32,
+}
+
+impl TrowbridgeReitz {
+    /// The distribution for a perceptual roughness in [0, 1], whose square
+    /// is α.
+    pub fn from_roughness(roughness: f32) -> Self {
+        Self {
+            alpha: roughness * roughness,
+        }
+    }
+
+    /// Below this, the surface is treated as a perfect mirror; the
+    /// distribution is too sharp to evaluate reliably.
+    pub fn is_smooth(self) -> bool {
+        self.alpha < 1e-3
+    }
+
+    /// Density of microfacet normals `wm`, per unit projected area.
+    pub fn d(self, wm: Vector) -> f32 {
+        let cos2 = wm.z * wm.z;
+        if cos2 == 0.0 {
+            return 0.0;
+        }
+        let tan2 = (1.0 - cos2) / cos2;
+        let α2 = self.alpha * self.alpha;
+        let e = 1.0 + tan2 / α2;
+        1.0 / (PI * α2 * cos2 * cos2 * e * e)
+    }
+
+    fn lambda(self, w: Vector) -> f32 {
+        let cos2 = w.z * w.z;
+        if cos2 == 0.0 {
+            return 0.0;
+        }
+        let tan2 = (1.0 - cos2) / cos2;
+        ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    /// Fraction of microfacets visible from `w`.
+    pub fn g1(self, w: Vector) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Fraction of microfacets visible from both `wo` and `wi`.
+    pub fn g(self, wo: Vector, wi: Vector) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of microfacet normals `wm` as seen from `w`, per solid
+    /// angle.
+    pub fn visible_d(self, w: Vector, wm: Vector) -> f32 {
+        if w.z == 0.0 {
+            return 0.0;
+        }
+        self.g1(w) / w.z.abs() * self.d(wm) * w.dot(wm).abs()
+    }
+
+    /// A microfacet normal visible from `w`, chosen with density
+    /// `visible_d` (Heitz 2018).
+    pub fn sample_visible_normal(self, w: Vector, u: (f32, f32)) -> Vector {
+        // Stretch to the configuration where α = 1 and the distribution is
+        // a hemisphere, sample the projected hemisphere, then unstretch.
+        let mut wh = Vector::new(self.alpha * w.x, self.alpha * w.y, w.z).normalize();
+        if wh.z < 0.0 {
+            wh = -wh;
+        }
+        let t1 = if wh.z < 0.99999 {
+            Vector::new(0., 0., 1.).cross(wh).normalize()
+        } else {
+            Vector::new(1., 0., 0.)
+        };
+        let t2 = wh.cross(t1);
+
+        let (px, py) = concentric_disk(u);
+        let h = (1.0 - px * px).sqrt();
+        let s = (1.0 + wh.z) / 2.0;
+        let py = (1.0 - s) * h + s * py;
+        let pz = (1.0 - px * px - py * py).max(0.0).sqrt();
+        let nh = t1 * px + t2 * py + wh * pz;
+
+        Vector::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(1e-6)).normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Pcg32;
+    use rand::prelude::*;
+
+    #[test]
+    fn normalized() {
+        // ∫ D(wm) cos θm dωm = 1 over the hemisphere, by uniform sampling.
+        let mut rng = Pcg32::new(1, 0);
+        for alpha in [0.1, 0.5, 1.0] {
+            let distribution = TrowbridgeReitz { alpha };
+            let n = 200_000;
+            let total: f32 = (0..n)
+                .map(|_| {
+                    let z: f32 = rng.gen();
+                    let r = (1.0 - z * z).sqrt();
+                    let φ = 2.0 * PI * rng.gen::<f32>();
+                    let wm = Vector::new(r * φ.cos(), r * φ.sin(), z);
+                    distribution.d(wm) * z
+                })
+                .sum();
+            let integral = total * 2.0 * PI / n as f32;
+            assert!((integral - 1.0).abs() < 0.05, "α = {}: {}", alpha, integral);
+        }
+    }
+
+    #[test]
+    fn visible_normals_face_the_viewer() {
+        let distribution = TrowbridgeReitz { alpha: 0.4 };
+        let w = Vector::new(0.6, 0.0, 0.8);
+        let mut rng = Pcg32::new(2, 0);
+        for _ in 0..1000 {
+            let wm = distribution.sample_visible_normal(w, rng.gen());
+            assert!((wm.magnitude() - 1.0).abs() < 1e-4);
+            assert!(wm.z > 0.0 && w.dot(wm) >= 0.0);
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    entity::Entity,
+    material::Material,
+    mesh::{Face, Mesh},
+    vec3::{Point, Vector},
+};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, io::Error),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            ObjError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl Error for ObjError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ObjError::Io(_, err) => Some(err),
+            ObjError::Parse { .. } => None,
+        }
+    }
+}
+
+/// Loads a Wavefront OBJ file as a single mesh entity.
+///
+/// Faces are assigned the material named by the most recent `usemtl`
+/// statement, looked up in `materials`. Faces before any `usemtl`, or
+/// naming a material that isn't in the map, use `default`.
+pub fn load(
+    path: impl AsRef<Path>,
+    materials: &HashMap<String, Material>,
+    default: Material,
+) -> Result<Entity, ObjError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| ObjError::Io(path.to_path_buf(), e))?;
+    parse(BufReader::new(file), path, materials, default)
+}
+
+/// Parses OBJ data from any reader. `path` is only used in error messages.
+pub fn parse<R: BufRead>(
+    reader: R,
+    path: &Path,
+    materials: &HashMap<String, Material>,
+    default: Material,
+) -> Result<Entity, ObjError> {
+    let mut parser = Parser {
+        path,
+        line: 0,
+        positions: vec![],
+        normals: vec![],
+        uvs: vec![],
+        faces: vec![],
+        materials: vec![default],
+        material_indices: HashMap::new(),
+        current_material: 0,
+    };
+
+    for line in reader.lines() {
+        parser.line += 1;
+        let line = line.map_err(|e| ObjError::Io(path.to_path_buf(), e))?;
+        parser.parse_line(&line, materials)?;
+    }
+
+    let mesh = Mesh::new(
+        parser.positions,
+        parser.normals,
+        parser.uvs,
+        parser.faces,
+        parser.materials,
+    );
+    Ok(Entity::Mesh(Arc::new(mesh)))
+}
+
+struct Parser<'a> {
+    path: &'a Path,
+    line: usize,
+    positions: Vec<Point>,
+    normals: Vec<Vector>,
+    uvs: Vec<(f32, f32)>,
+    faces: Vec<Face>,
+    materials: Vec<Material>,
+    material_indices: HashMap<String, usize>,
+    current_material: usize,
+}
+
+struct FaceVertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+impl Parser<'_> {
+    fn parse_line(
+        &mut self,
+        line: &str,
+        materials: &HashMap<String, Material>,
+    ) -> Result<(), ObjError> {
+        let line = match line.find('#') {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => return Ok(()),
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = self.floats(&args, 3, 4)?;
+                self.positions.push(Point::new(x, y, z));
+            }
+            "vn" => {
+                let [x, y, z] = self.floats(&args, 3, 3)?;
+                self.normals.push(Vector::new(x, y, z));
+            }
+            "vt" => {
+                let [u, v, _] = self.floats(&args, 1, 3)?;
+                self.uvs.push((u, v));
+            }
+            "f" => self.face(&args)?,
+            "usemtl" => {
+                let name = args.join(" ");
+                self.current_material = match materials.get(&name) {
+                    Some(material) => *self.material_indices.entry(name).or_insert_with(|| {
+                        self.materials.push(material.clone());
+                        self.materials.len() - 1
+                    }),
+                    None => 0,
+                };
+            }
+            // Grouping, smoothing, material libraries and other statements
+            // carry nothing the mesh needs.
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Parses between `min` and `max` floats, padding missing optional
+    /// components with zero.
+    fn floats(&self, args: &[&str], min: usize, max: usize) -> Result<[f32; 3], ObjError> {
+        if args.len() < min || args.len() > max {
+            return Err(self.error(format!(
+                "expected {} to {} numbers, found {}",
+                min,
+                max,
+                args.len()
+            )));
+        }
+        let mut values = [0.0; 3];
+        for (value, arg) in values.iter_mut().zip(args) {
+            *value = arg
+                .parse()
+                .map_err(|_| self.error(format!("invalid number `{}`", arg)))?;
+        }
+        Ok(values)
+    }
+
+    fn face(&mut self, args: &[&str]) -> Result<(), ObjError> {
+        if args.len() < 3 {
+            return Err(self.error(format!(
+                "a face needs at least 3 vertices, found {}",
+                args.len()
+            )));
+        }
+        let vertices = args
+            .iter()
+            .map(|arg| self.face_vertex(arg))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Attributes are only used when every corner of the face has them.
+        let has_uvs = vertices.iter().all(|v| v.uv.is_some());
+        let has_normals = vertices.iter().all(|v| v.normal.is_some());
+
+        // Fan triangulation, which is exact for the convex polygons OBJ
+        // exporters produce.
+        for i in 1..vertices.len() - 1 {
+            let corners = [&vertices[0], &vertices[i], &vertices[i + 1]];
+            self.faces.push(Face {
+                positions: corners.map(|v| v.position),
+                uvs: has_uvs.then(|| corners.map(|v| v.uv.unwrap())),
+                normals: has_normals.then(|| corners.map(|v| v.normal.unwrap())),
+                material: self.current_material,
+            });
+        }
+        Ok(())
+    }
+
+    fn face_vertex(&self, arg: &str) -> Result<FaceVertex, ObjError> {
+        let mut parts = arg.split('/');
+        let position = self.index(parts.next(), self.positions.len(), arg)?;
+        let uv = self.index(parts.next(), self.uvs.len(), arg)?;
+        let normal = self.index(parts.next(), self.normals.len(), arg)?;
+
+        if parts.next().is_some() {
+            return Err(self.error(format!("invalid face vertex `{}`", arg)));
+        }
+        match position {
+            Some(position) => Ok(FaceVertex {
+                position,
+                uv,
+                normal,
+            }),
+            None => Err(self.error(format!("face vertex `{}` has no position", arg))),
+        }
+    }
+
+    /// Resolves a 1-based (or negative, relative) OBJ index against the
+    /// number of elements defined so far.
+    fn index(&self, part: Option<&str>, len: usize, arg: &str) -> Result<Option<usize>, ObjError> {
+        let part = match part {
+            None | Some("") => return Ok(None),
+            Some(part) => part,
+        };
+        let index: i64 = part
+            .parse()
+            .map_err(|_| self.error(format!("invalid index in face vertex `{}`", arg)))?;
+        let resolved = if index > 0 {
+            index - 1
+        } else {
+            len as i64 + index
+        };
+        if index == 0 || resolved < 0 || resolved >= len as i64 {
+            return Err(self.error(format!("index {} out of range in `{}`", index, arg)));
+        }
+        Ok(Some(resolved as usize))
+    }
+
+    fn error(&self, message: String) -> ObjError {
+        ObjError::Parse {
+            path: self.path.to_path_buf(),
+            line: self.line,
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    fn parse_str(source: &str) -> Result<Entity, ObjError> {
+        let mut materials = HashMap::new();
+        materials.insert(
+            "red".to_string(),
+            Material::Lambertian(Color::new(1., 0., 0.).into()),
+        );
+        parse(
+            source.as_bytes(),
+            Path::new("test.obj"),
+            &materials,
+            Material::Lambertian(Color::white().into()),
+        )
+    }
+
+    fn mesh(entity: Entity) -> Arc<Mesh> {
+        match entity {
+            Entity::Mesh(mesh) => mesh,
+            _ => panic!("expected a mesh"),
+        }
+    }
+
+    #[test]
+    fn triangulates_polygons() {
+        let mesh = mesh(
+            parse_str(
+                "# a unit square\n\
+                 v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+                 vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\n\
+                 vn 0 0 1\n\
+                 f 1/1/1 2/2/1 3/3/1 4/4/1\n",
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(mesh.faces.len(), 2);
+        assert_eq!(mesh.faces[0].positions, [0, 1, 2]);
+        assert_eq!(mesh.faces[1].positions, [0, 2, 3]);
+        assert_eq!(mesh.faces[1].uvs, Some([0, 2, 3]));
+        assert_eq!(mesh.faces[1].normals, Some([0, 0, 0]));
+    }
+
+    #[test]
+    fn relative_indices_and_materials() {
+        let mesh = mesh(
+            parse_str(
+                "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+                 f 1 2 3\n\
+                 usemtl red\n\
+                 f -3//  -2 -1\n",
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(mesh.faces.len(), 2);
+        assert_eq!(mesh.faces[1].positions, [0, 1, 2]);
+        assert_eq!(mesh.faces[0].material, 0);
+        assert_eq!(mesh.faces[1].material, 1);
+        assert_eq!(mesh.materials.len(), 2);
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let err = parse_str("v 0 0 0\nv 1 0 0\n\nf 1 2 3\n").err().unwrap();
+        assert_eq!(err.to_string(), "test.obj:4: index 3 out of range in `3`");
+
+        let err = parse_str("v 0 zero 0\n").err().unwrap();
+        assert_eq!(err.to_string(), "test.obj:1: invalid number `zero`");
+    }
+}
@@ -0,0 +1,151 @@
+use rand::prelude::*;
+
+use crate::{
+    rng::Pcg32,
+    vec3::{Point, Vector},
+};
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise over a 256-entry lattice of random unit vectors, the
+/// same for any given seed.
+pub struct Perlin {
+    gradients: Vec<Vector>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Pcg32::new(seed, 0);
+        let gradients = (0..POINT_COUNT)
+            .map(|_| Vector::random_normalized(&mut rng))
+            .collect();
+        let mut permutation = || {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            p.shuffle(&mut rng);
+            p
+        };
+        Self {
+            gradients,
+            perm_x: permutation(),
+            perm_y: permutation(),
+            perm_z: permutation(),
+        }
+    }
+
+    /// Smoothly interpolated noise in roughly [-1, 1], zero at every
+    /// lattice point.
+    pub fn noise(&self, p: Point) -> f32 {
+        let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (u, v, w) = (p.x - fx, p.y - fy, p.z - fz);
+        let (i, j, k) = (fx as i64, fy as i64, fz as i64);
+
+        let mut corners = [[[Vector::default(); 2]; 2]; 2];
+        for (di, plane) in corners.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let hash = self.perm_x[wrap(i + di as i64)]
+                        ^ self.perm_y[wrap(j + dj as i64)]
+                        ^ self.perm_z[wrap(k + dk as i64)];
+                    *corner = self.gradients[hash];
+                }
+            }
+        }
+        trilinear(&corners, u, v, w)
+    }
+
+    /// Sum of `octaves` layers of noise, each at `lacunarity` times the
+    /// frequency and `gain` times the amplitude of the previous one.
+    pub fn fbm(&self, p: Point, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+        let mut sum = 0.0;
+        let mut p = p;
+        let mut amplitude = 1.0;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(p);
+            p = p * lacunarity;
+            amplitude *= gain;
+        }
+        sum
+    }
+
+    /// Like `fbm` with the usual doubling frequency and halving amplitude,
+    /// but summing absolute values for the creased look of turbulence.
+    pub fn turbulence(&self, p: Point, octaves: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut p = p;
+        let mut amplitude = 1.0;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(p).abs();
+            p = p * 2.0;
+            amplitude *= 0.5;
+        }
+        sum
+    }
+}
+
+fn wrap(i: i64) -> usize {
+    (i & (POINT_COUNT as i64 - 1)) as usize
+}
+
+/// Hermite-smoothed trilinear blend of the corner gradients' dot products
+/// with the offset to the sample point.
+fn trilinear(corners: &[[[Vector; 2]; 2]; 2], u: f32, v: f32, w: f32) -> f32 {
+    let hermite = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (uu, vv, ww) = (hermite(u), hermite(v), hermite(w));
+
+    let mut sum = 0.0;
+    for (i, plane) in corners.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, gradient) in row.iter().enumerate() {
+                let (i, j, k) = (i as f32, j as f32, k as f32);
+                let offset = Vector::new(u - i, v - j, w - k);
+                sum += (i * uu + (1.0 - i) * (1.0 - uu))
+                    * (j * vv + (1.0 - j) * (1.0 - vv))
+                    * (k * ww + (1.0 - k) * (1.0 - ww))
+                    * gradient.dot(offset);
+            }
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_at_lattice_points() {
+        let perlin = Perlin::new(1);
+
+        assert_eq!(perlin.noise(Point::new(0., 0., 0.)), 0.0);
+        assert_eq!(perlin.noise(Point::new(3., -7., 12.)), 0.0);
+    }
+
+    #[test]
+    fn bounded() {
+        let perlin = Perlin::new(2);
+
+        for i in 0..1000 {
+            let p = Point::new(i as f32 * 0.37, i as f32 * -0.11, i as f32 * 0.053);
+            assert!(perlin.noise(p).abs() <= 1.0);
+            assert!(perlin.turbulence(p, 7) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn continuous() {
+        let perlin = Perlin::new(3);
+        let p = Point::new(1.3, 2.7, -0.4);
+        let δ = Vector::new(1e-3, 1e-3, 1e-3);
+
+        assert!((perlin.noise(p) - perlin.noise(p + δ)).abs() < 1e-2);
+    }
+}
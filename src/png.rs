@@ -0,0 +1,171 @@
+use std::io::{self, Write};
+
+use crate::{
+    framebuffer::{Framebuffer, WriteOptions},
+    zlib,
+};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum BitDepth {
+    #[default]
+    Eight,
+    Sixteen,
+}
+
+/// Writes an RGB PNG tagged as sRGB, after the display transform in
+/// `options`.
+pub fn write<W: Write>(w: &mut W, fb: &Framebuffer, options: &WriteOptions) -> io::Result<()> {
+    let (width, height) = (fb.width, fb.height);
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    let (bits, bytes_per_pixel) = match options.bit_depth {
+        BitDepth::Eight => (8, 3),
+        BitDepth::Sixteen => (16, 6),
+    };
+    // Truecolor, deflate, adaptive filtering, no interlacing.
+    header.extend_from_slice(&[bits, 2, 0, 0, 0]);
+
+    let stride = width as usize * bytes_per_pixel;
+    let mut raw = Vec::with_capacity(height as usize * stride);
+    for row in fb.pixels.chunks(width as usize) {
+        for &color in row {
+            let c = options.display(color);
+            for v in [c.x, c.y, c.z] {
+                match options.bit_depth {
+                    BitDepth::Eight => raw.push((v * 255.0).round() as u8),
+                    BitDepth::Sixteen => {
+                        raw.extend_from_slice(&((v * 65535.0).round() as u16).to_be_bytes())
+                    }
+                }
+            }
+        }
+    }
+
+    w.write_all(&SIGNATURE)?;
+    write_chunk(w, b"IHDR", &header)?;
+    // Perceptual rendering intent.
+    write_chunk(w, b"sRGB", &[0])?;
+    write_chunk(
+        w,
+        b"IDAT",
+        &zlib::compress(&filter(&raw, stride, bytes_per_pixel)),
+    )?;
+    write_chunk(w, b"IEND", &[])
+}
+
+fn write_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let mut body = Vec::with_capacity(data.len() + 4);
+    body.extend_from_slice(kind);
+    body.extend_from_slice(data);
+
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(&body)?;
+    w.write_all(&zlib::crc32(&body).to_be_bytes())
+}
+
+/// Prefixes each scanline with whichever filter leaves it with the
+/// smallest sum of absolute differences, the usual heuristic for what
+/// will compress best.
+fn filter(raw: &[u8], stride: usize, bpp: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(raw.len() + raw.len() / stride);
+    let zeros = vec![0; stride];
+    let mut candidate = vec![0; stride];
+    let mut best = vec![0; stride];
+
+    for (y, row) in raw.chunks(stride).enumerate() {
+        let above = if y == 0 {
+            &zeros[..]
+        } else {
+            &raw[(y - 1) * stride..y * stride]
+        };
+
+        let mut best_kind = 0;
+        let mut best_cost = u64::MAX;
+        for kind in 0..5u8 {
+            for i in 0..stride {
+                let a = if i >= bpp { row[i - bpp] } else { 0 };
+                let b = above[i];
+                let c = if i >= bpp { above[i - bpp] } else { 0 };
+                let predicted = match kind {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    _ => paeth(a, b, c),
+                };
+                candidate[i] = row[i].wrapping_sub(predicted);
+            }
+            let cost = candidate
+                .iter()
+                .map(|&v| (v as i8).unsigned_abs() as u64)
+                .sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best_kind = kind;
+                best.copy_from_slice(&candidate);
+            }
+        }
+        out.push(best_kind);
+        out.extend_from_slice(&best);
+    }
+    out
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn chunks() {
+        let mut bytes = vec![];
+        let fb = Framebuffer {
+            width: 2,
+            height: 1,
+            pixels: vec![Color::white(), Color::new(0.5, 0., 0.)],
+        };
+        let options = WriteOptions {
+            bit_depth: BitDepth::Sixteen,
+            ..Default::default()
+        };
+        write(&mut bytes, &fb, &options).unwrap();
+
+        assert_eq!(&bytes[..8], &SIGNATURE);
+        assert_eq!(&bytes[8..16], b"\0\0\0\x0dIHDR");
+        assert_eq!(&bytes[16..29], &[0, 0, 0, 2, 0, 0, 0, 1, 16, 2, 0, 0, 0]);
+        assert_eq!(&bytes[29..33], &zlib::crc32(&bytes[12..29]).to_be_bytes());
+        assert_eq!(&bytes[33..42], b"\0\0\0\x01sRGB\0");
+        assert_eq!(&bytes[bytes.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+    }
+
+    #[test]
+    fn filters() {
+        // On a ramp Sub wins for the first row and Paeth for the second.
+        let raw: Vec<u8> = (0..12).collect();
+        let filtered = filter(&raw, 6, 3);
+
+        assert_eq!(filtered, [1, 0, 1, 2, 3, 3, 3, 4, 6, 6, 6, 3, 3, 3]);
+        assert_eq!(paeth(10, 20, 15), 15);
+        assert_eq!(paeth(10, 20, 10), 20);
+    }
+}
@@ -0,0 +1,15 @@
+use std::io::{self, Write};
+
+use crate::framebuffer::{Framebuffer, WriteOptions};
+
+/// Writes an 8-bit ASCII (P3) PPM, after the display transform in
+/// `options`.
+pub fn write<W: Write>(w: &mut W, fb: &Framebuffer, options: &WriteOptions) -> io::Result<()> {
+    writeln!(w, "P3\n{} {}\n255", fb.width, fb.height)?;
+    for &color in &fb.pixels {
+        let c = options.display(color);
+        let byte = |v: f32| (v * 255.0).round() as u8;
+        writeln!(w, "{} {} {}", byte(c.x), byte(c.y), byte(c.z))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use indicatif::ProgressBar;
+
+/// Receives progress updates while a render runs. Updates arrive from many
+/// threads at once.
+pub trait Progress: Sync {
+    /// Called once before any work with the number of units to expect.
+    fn start(&self, _total: u64) {}
+    fn advance(&self, units: u64);
+    fn finish(&self) {}
+}
+
+/// Ignores all updates.
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn advance(&self, _units: u64) {}
+}
+
+impl Progress for ProgressBar {
+    fn start(&self, total: u64) {
+        self.set_length(total);
+    }
+
+    fn advance(&self, units: u64) {
+        self.inc(units);
+    }
+
+    fn finish(&self) {
+        ProgressBar::finish(self);
+    }
+}
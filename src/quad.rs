@@ -0,0 +1,138 @@
+use crate::{
+    aabb::Aabb,
+    hit_record::HitRecord,
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vec3::{Point, Vector},
+};
+
+/// A parallelogram spanned by the edges `u` and `v` from the corner `q`.
+#[derive(Clone, Copy)]
+pub struct Quad {
+    pub q: Point,
+    pub u: Vector,
+    pub v: Vector,
+    normal: Vector,
+    d: f32,
+    w: Vector,
+}
+
+impl Quad {
+    pub fn new(q: Point, u: Vector, v: Vector) -> Self {
+        let n = u.cross(v);
+        let normal = n.normalize();
+        let d = normal.dot(q);
+        let w = n / n.dot(n);
+        Self {
+            q,
+            u,
+            v,
+            normal,
+            d,
+            w,
+        }
+    }
+
+    /// The unit normal, on the side `u` turns toward `v` counterclockwise.
+    pub fn normal(&self) -> Vector {
+        self.normal
+    }
+
+    pub fn area(&self) -> f32 {
+        self.u.cross(self.v).magnitude()
+    }
+
+    pub fn hit<'a>(
+        &self,
+        ray: &Ray,
+        interval: &Interval,
+        material: &'a Material,
+    ) -> Option<HitRecord<'a>> {
+        let denominator = self.normal.dot(ray.direction);
+
+        // Rays parallel to the plane never hit it.
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(ray.origin)) / denominator;
+        if !interval.contains(t) {
+            return None;
+        }
+
+        // Express the hit point in the plane's (u, v) basis; those
+        // coordinates double as the surface UVs.
+        let p = ray.at(t);
+        let planar = p - self.q;
+        let alpha = self.w.dot(planar.cross(self.v));
+        let beta = self.w.dot(self.u.cross(planar));
+
+        let unit = Interval::new(0.0, 1.0);
+        if !unit.contains(alpha) || !unit.contains(beta) {
+            return None;
+        }
+
+        Some(HitRecord::new(
+            p,
+            self.normal,
+            t,
+            (alpha, beta),
+            ray,
+            material,
+        ))
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        Aabb::surrounding(
+            Aabb::from_points(self.q, self.q + self.u + self.v),
+            Aabb::from_points(self.q + self.u, self.q + self.v),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    fn quad() -> Quad {
+        Quad::new(
+            Point::new(-1., -1., 0.),
+            Vector::new(2., 0., 0.),
+            Vector::new(0., 4., 0.),
+        )
+    }
+
+    #[test]
+    fn hit() {
+        let q = quad();
+        let i = Interval::new(0.001, f32::INFINITY);
+        let m = Material::Lambertian(Color::white().into());
+
+        let r = Ray::new(Point::new(0.5, 2., 5.), Vector::new(0., 0., -1.), 0.0);
+        let rec = q.hit(&r, &i, &m).unwrap();
+        assert_eq!(rec.t, 5.0);
+        assert_eq!(rec.p, Point::new(0.5, 2., 0.));
+        assert_eq!(rec.u, 0.75);
+        assert_eq!(rec.v, 0.75);
+        assert!(rec.front_face);
+
+        let r = Ray::new(Point::new(1.5, 0., 5.), Vector::new(0., 0., -1.), 0.0);
+        assert!(q.hit(&r, &i, &m).is_none());
+
+        let r = Ray::new(Point::new(0., 0., 5.), Vector::new(1., 0., 0.), 0.0);
+        assert!(q.hit(&r, &i, &m).is_none());
+    }
+
+    #[test]
+    fn bounding_box() {
+        let b = quad().bounding_box();
+
+        assert_eq!(b.x.min, -1.);
+        assert_eq!(b.x.max, 1.);
+        assert_eq!(b.y.min, -1.);
+        assert_eq!(b.y.max, 3.);
+        assert!(b.z.size() > 0.0);
+    }
+}
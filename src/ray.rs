@@ -1,9 +1,13 @@
 use crate::vec3::{Point, Vector};
 
+#[derive(Clone, Copy)]
 pub struct Ray {
     pub origin: Point,
     pub direction: Vector,
     pub time: f32,
+    /// A uniform value that, keyed on each medium the ray passes through,
+    /// chooses where it scatters there.
+    pub free_flight: f32,
 }
 
 impl Ray {
@@ -12,6 +16,7 @@ impl Ray {
             origin,
             direction,
             time,
+            free_flight: 0.5,
         }
     }
 
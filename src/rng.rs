@@ -0,0 +1,116 @@
+use rand::{Error, RngCore};
+
+const MULTIPLIER: u64 = 6364136223846793005;
+
+/// O'Neill's PCG32 (XSH RR): 64 bits of state, 32-bit output, and a
+/// selectable stream. Small and fast enough to create one per sample, which
+/// is what makes renders repeatable regardless of how rayon schedules
+/// them.
+#[derive(Clone, Debug)]
+pub struct Pcg32 {
+    state: u64,
+    increment: u64,
+}
+
+impl Pcg32 {
+    /// The generator for `seed` on the given stream, seeded the same way as
+    /// the reference `pcg32_srandom`.
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        rng.step();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.step();
+        rng
+    }
+
+    /// The generator for one sample of one pixel of a render seeded with
+    /// `seed`.
+    pub fn for_sample(seed: u64, pixel: u64, sample: u64) -> Self {
+        Self::new(mix(seed ^ mix(sample)), pixel)
+    }
+
+    /// A generator keyed on arbitrary words, such as the bits of a ray.
+    pub fn hashed(words: &[u64]) -> Self {
+        let seed = words.iter().fold(0, |h, &w| mix(h ^ w));
+        Self::new(seed, mix(seed))
+    }
+
+    fn step(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+}
+
+impl RngCore for Pcg32 {
+    fn next_u32(&mut self) -> u32 {
+        self.step()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = self.step() as u64;
+        (self.step() as u64) << 32 | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.step().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// The SplitMix64 finalizer, used to spread nearby keys across the seed
+/// space.
+pub(crate) fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn reference_output() {
+        // From the PCG distribution's pcg32-demo.
+        let mut rng = Pcg32::new(42, 54);
+        let expected = [0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b];
+        for e in expected {
+            assert_eq!(rng.next_u32(), e);
+        }
+    }
+
+    #[test]
+    fn keyed() {
+        let draw = |mut rng: Pcg32| -> Vec<f32> { (0..4).map(|_| rng.gen()).collect() };
+        assert_eq!(
+            draw(Pcg32::for_sample(7, 3, 1)),
+            draw(Pcg32::for_sample(7, 3, 1))
+        );
+        assert_ne!(
+            draw(Pcg32::for_sample(7, 3, 1)),
+            draw(Pcg32::for_sample(7, 3, 2))
+        );
+        assert_ne!(
+            draw(Pcg32::for_sample(7, 3, 1)),
+            draw(Pcg32::for_sample(8, 3, 1))
+        );
+        assert_ne!(
+            draw(Pcg32::for_sample(7, 3, 1)),
+            draw(Pcg32::for_sample(7, 4, 1))
+        );
+    }
+}
@@ -0,0 +1,382 @@
+use std::f32::consts::PI;
+
+use rand::prelude::*;
+
+use crate::{
+    rng::{mix, Pcg32},
+    vec3::Vector,
+};
+
+const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+/// How the random numbers for each pixel sample are chosen. Every sample
+/// draws its values in the same order (pixel, lens, time, wavelength when
+/// rendering spectrally, then the same block per bounce whether or not
+/// each value gets used), so the sampler can spread each of those
+/// dimensions evenly across the pixel's samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Sampler {
+    /// Uniform random numbers, with nothing shared between samples.
+    Independent,
+    /// Jittered points on a √n × √n grid, with the strata shuffled
+    /// separately for each dimension.
+    Stratified,
+    /// The Halton sequence, Owen scrambled per pixel and dimension.
+    Halton,
+    /// Burley's shuffled, Owen-scrambled Sobol (0, 2) sequence, padded to
+    /// any number of dimensions. Best at power-of-two sample counts.
+    #[default]
+    Sobol,
+}
+
+impl Sampler {
+    /// The values for sample `index` of `samples_per_pixel` in a pixel.
+    pub fn start(self, seed: u64, pixel: u64, index: u32, samples_per_pixel: u32) -> PixelSample {
+        PixelSample {
+            sampler: self,
+            key: mix(seed ^ mix(pixel)),
+            index,
+            count: samples_per_pixel.max(1),
+            dimension: 0,
+            rng: Pcg32::for_sample(seed, pixel, index as u64),
+        }
+    }
+}
+
+/// The stream of sample values for one pixel sample, handed out one or two
+/// dimensions at a time.
+pub struct PixelSample {
+    sampler: Sampler,
+    key: u64,
+    index: u32,
+    count: u32,
+    dimension: u32,
+    rng: Pcg32,
+}
+
+impl PixelSample {
+    pub fn get_1d(&mut self) -> f32 {
+        let dimension = self.next_dimension(1);
+        let hash = mix(self.key ^ dimension as u64);
+        match self.sampler {
+            Sampler::Independent => self.rng.gen(),
+            Sampler::Stratified => {
+                let stratum = permutation_element(self.index, self.count, hash as u32);
+                (stratum as f32 + self.rng.gen::<f32>()) / self.count as f32
+            }
+            Sampler::Halton => self.halton(dimension, hash),
+            Sampler::Sobol => {
+                let index = nested_uniform_scramble(self.index, hash as u32);
+                to_unit(nested_uniform_scramble(
+                    index.reverse_bits(),
+                    (hash >> 32) as u32,
+                ))
+            }
+        }
+    }
+
+    pub fn get_2d(&mut self) -> (f32, f32) {
+        let dimension = self.next_dimension(2);
+        let hash = mix(self.key ^ dimension as u64);
+        match self.sampler {
+            Sampler::Independent => (self.rng.gen(), self.rng.gen()),
+            Sampler::Stratified => {
+                let side = (self.count as f32).sqrt() as u32;
+                if self.index >= side * side {
+                    // Left over once the grid is full.
+                    return (self.rng.gen(), self.rng.gen());
+                }
+                let cell = permutation_element(self.index, side * side, hash as u32);
+                (
+                    ((cell % side) as f32 + self.rng.gen::<f32>()) / side as f32,
+                    ((cell / side) as f32 + self.rng.gen::<f32>()) / side as f32,
+                )
+            }
+            Sampler::Halton => (
+                self.halton(dimension, hash),
+                self.halton(dimension + 1, mix(hash)),
+            ),
+            Sampler::Sobol => {
+                let index = nested_uniform_scramble(self.index, hash as u32);
+                let hash = mix(hash);
+                (
+                    to_unit(nested_uniform_scramble(index.reverse_bits(), hash as u32)),
+                    to_unit(nested_uniform_scramble(
+                        sobol_second(index),
+                        (hash >> 32) as u32,
+                    )),
+                )
+            }
+        }
+    }
+
+    fn next_dimension(&mut self, count: u32) -> u32 {
+        let dimension = self.dimension;
+        self.dimension += count;
+        dimension
+    }
+
+    /// Halton dimensions past the prime table fall back to random numbers.
+    fn halton(&mut self, dimension: u32, hash: u64) -> f32 {
+        match PRIMES.get(dimension as usize) {
+            Some(&base) => {
+                owen_scrambled_radical_inverse(base, self.index as u64, self.count, hash as u32)
+            }
+            None => self.rng.gen(),
+        }
+    }
+}
+
+/// A point in the unit disk, mapping the square to it with little
+/// distortion so stratification carries over (Shirley and Chiu).
+pub fn concentric_disk(u: (f32, f32)) -> (f32, f32) {
+    let (a, b) = (2.0 * u.0 - 1.0, 2.0 * u.1 - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (r, θ) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    (r * θ.cos(), r * θ.sin())
+}
+
+/// A cosine-weighted direction about +z.
+pub fn cosine_hemisphere(u: (f32, f32)) -> Vector {
+    let (x, y) = concentric_disk(u);
+    Vector::new(x, y, (1.0 - x * x - y * y).max(0.0).sqrt())
+}
+
+/// A uniformly distributed unit vector.
+pub fn uniform_sphere(u: (f32, f32)) -> Vector {
+    let z = 1.0 - 2.0 * u.0;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let φ = 2.0 * PI * u.1;
+    Vector::new(r * φ.cos(), r * φ.sin(), z)
+}
+
+fn to_unit(bits: u32) -> f32 {
+    (bits >> 8) as f32 / (1u32 << 24) as f32
+}
+
+/// The second Sobol dimension, whose generator matrix is Pascal's triangle
+/// mod 2 (the first is just bit reversal).
+fn sobol_second(index: u32) -> u32 {
+    let mut x = 0;
+    let mut v = 1u32 << 31;
+    let mut index = index;
+    while index != 0 {
+        if index & 1 != 0 {
+            x ^= v;
+        }
+        index >>= 1;
+        v ^= v >> 1;
+    }
+    x
+}
+
+/// Owen scrambling of all 32 bits, from the hash-based permutation in
+/// Burley's "Practical Hash-based Owen Scrambling".
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    let mut x = x.reverse_bits();
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x.reverse_bits()
+}
+
+/// The radical inverse of `a` in `base`, with every digit permuted by a
+/// hash of the digits above it. `a` is one of `count` indices.
+fn owen_scrambled_radical_inverse(base: u32, mut a: u64, count: u32, hash: u32) -> f32 {
+    let inv_base = 1.0 / base as f32;
+    let mut inv_base_m = 1.0f32;
+    let mut reversed: u64 = 0;
+    while 1.0 - inv_base_m < 1.0 {
+        if a == 0 && inv_base_m * count as f32 <= 1.0 {
+            // Past the digits that tell the pixel's samples apart, the rest
+            // are zeros each permuted by a hash of a distinct prefix:
+            // together, just a uniform random tail.
+            let tail = to_unit(mix(hash as u64 ^ reversed) as u32);
+            return (inv_base_m * (reversed as f32 + tail)).min(ONE_MINUS_EPSILON);
+        }
+        let next = a / base as u64;
+        let digit = (a - next * base as u64) as u32;
+        let digit_hash = mix(hash as u64 ^ reversed) as u32;
+        reversed = reversed * base as u64 + permutation_element(digit, base, digit_hash) as u64;
+        inv_base_m *= inv_base;
+        a = next;
+    }
+    (inv_base_m * reversed as f32).min(ONE_MINUS_EPSILON)
+}
+
+/// Element `i` of a random permutation of 0..`length` chosen by `p`,
+/// without building the permutation (Kensler, "Correlated Multi-Jittered
+/// Sampling").
+fn permutation_element(mut i: u32, length: u32, p: u32) -> u32 {
+    let mut w = length - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+    i.wrapping_add(p) % length
+}
+
+const PRIMES: [u32; 256] = primes();
+
+const fn primes<const N: usize>() -> [u32; N] {
+    let mut primes = [0; N];
+    let mut count = 0;
+    let mut candidate = 2;
+    while count < N {
+        let mut i = 0;
+        let mut is_prime = true;
+        while i < count && primes[i] * primes[i] <= candidate {
+            if candidate % primes[i] == 0 {
+                is_prime = false;
+                break;
+            }
+            i += 1;
+        }
+        if is_prime {
+            primes[count] = candidate;
+            count += 1;
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Draw = ((f32, f32), f32, (f32, f32));
+
+    /// Draws the first few dimensions of every sample in a pixel.
+    fn draw(sampler: Sampler, count: u32) -> Vec<Draw> {
+        (0..count)
+            .map(|i| {
+                let mut s = sampler.start(9, 17, i, count);
+                (s.get_2d(), s.get_1d(), s.get_2d())
+            })
+            .collect()
+    }
+
+    /// Whether each of `n` equal bins of [0, 1) holds exactly one value.
+    fn one_per_bin(values: impl Iterator<Item = f32>, n: usize) -> bool {
+        let mut bins = vec![0; n];
+        for v in values {
+            assert!((0.0..1.0).contains(&v));
+            bins[(v * n as f32) as usize] += 1;
+        }
+        bins.iter().all(|&b| b == 1)
+    }
+
+    /// Which cell of an n × n grid each point falls in, as a value in
+    /// the bin of that index.
+    fn grid(u: (f32, f32), n: f32) -> f32 {
+        ((u.1 * n).floor() * n + (u.0 * n).floor()) / (n * n)
+    }
+
+    #[test]
+    fn stratified() {
+        let samples = draw(Sampler::Stratified, 16);
+        assert!(one_per_bin(samples.iter().map(|s| grid(s.0, 4.0)), 16));
+        assert!(one_per_bin(samples.iter().map(|s| s.1), 16));
+        assert!(one_per_bin(samples.iter().map(|s| grid(s.2, 4.0)), 16));
+    }
+
+    #[test]
+    fn halton() {
+        assert!(one_per_bin(
+            draw(Sampler::Halton, 16).iter().map(|s| s.0 .0),
+            16
+        ));
+        assert!(one_per_bin(
+            draw(Sampler::Halton, 9).iter().map(|s| s.0 .1),
+            9
+        ));
+        assert!(one_per_bin(
+            draw(Sampler::Halton, 25).iter().map(|s| s.1),
+            25
+        ));
+    }
+
+    #[test]
+    fn sobol() {
+        let samples = draw(Sampler::Sobol, 16);
+        let first: Vec<_> = samples.iter().map(|s| s.0).collect();
+        let second: Vec<_> = samples.iter().map(|s| s.2).collect();
+        for s2 in [first, second] {
+            assert!(one_per_bin(s2.iter().map(|u| u.0), 16));
+            assert!(one_per_bin(s2.iter().map(|u| u.1), 16));
+            assert!(one_per_bin(s2.iter().map(|&u| grid(u, 4.0)), 16));
+        }
+        assert!(one_per_bin(samples.iter().map(|s| s.1), 16));
+    }
+
+    #[test]
+    fn repeatable_and_keyed_on_the_pixel() {
+        for sampler in [
+            Sampler::Independent,
+            Sampler::Stratified,
+            Sampler::Halton,
+            Sampler::Sobol,
+        ] {
+            let a = sampler.start(1, 2, 3, 16).get_2d();
+            assert_eq!(a, sampler.start(1, 2, 3, 16).get_2d());
+            assert_ne!(a, sampler.start(1, 5, 3, 16).get_2d());
+        }
+    }
+
+    #[test]
+    fn permutations() {
+        for length in [1, 5, 16, 100] {
+            let mut seen: Vec<u32> = (0..length)
+                .map(|i| permutation_element(i, length, 1234))
+                .collect();
+            seen.sort();
+            assert_eq!(seen, (0..length).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn mappings() {
+        assert_eq!(&PRIMES[..5], &[2, 3, 5, 7, 11]);
+        let mut rng = Pcg32::new(1, 0);
+        for _ in 0..1000 {
+            let u = (rng.gen(), rng.gen());
+            let (x, y) = concentric_disk(u);
+            assert!(x * x + y * y <= 1.0 + 1e-6);
+            assert!((cosine_hemisphere(u).magnitude() - 1.0).abs() < 1e-5);
+            assert!((uniform_sphere(u).magnitude() - 1.0).abs() < 1e-5);
+        }
+    }
+}
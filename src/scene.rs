@@ -0,0 +1,1361 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use toml::{Table, Value};
+
+use crate::{
+    adaptive::AdaptiveSampling,
+    camera::{Background, Camera},
+    color::Color,
+    dispersion::RefractionIndex,
+    entity::Entity,
+    exr,
+    film::PixelFilter,
+    image::{Filter, Image, Wrap},
+    material::{absorption_coefficient, Material},
+    microfacet::TrowbridgeReitz,
+    obj,
+    png::BitDepth,
+    sampler::Sampler,
+    spectrum::{LAMBDA_MAX, LAMBDA_MIN},
+    texture::Texture,
+    tonemap::ToneMap,
+    vec3::{Mat4, Point, Vector},
+    world::World,
+};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(PathBuf, io::Error),
+    Syntax(PathBuf, String),
+    /// A value in the scene is missing, malformed or refers to something
+    /// that doesn't exist. `key` is the dotted path to it, such as
+    /// `entities[2].material.albedo`.
+    Invalid {
+        key: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            SceneError::Syntax(path, message) => write!(f, "{}: {}", path.display(), message),
+            SceneError::Invalid { key, message } => write!(f, "`{}`: {}", key, message),
+        }
+    }
+}
+
+impl Error for SceneError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SceneError::Io(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, SceneError>;
+
+/// Loads a TOML scene description.
+///
+/// A scene has an optional `[camera]` table, optional `[textures]` and
+/// `[materials]` tables of named definitions, and an `[[entities]]` array:
+///
+/// ```toml
+/// [camera]
+/// image_width = 400
+/// lookfrom = [13, 2, 3]
+/// background = "black"          # or "gradient", [r, g, b], or a table
+/// exposure = 1.5                # stops, for PPM and PNG output
+/// tone_map = "aces"             # clamp, reinhard, hable, agx, or
+///                               # { type = "extended_reinhard", white = 4 }
+/// bit_depth = 16                # for PNG output; 8 by default
+/// exr_pixel_type = "float"      # or "half", the default
+/// exr_compression = "none"      # or "zip", the default
+/// russian_roulette_depth = 3    # bounces before paths may end early;
+///                               # off by default, or with false or 0
+/// seed = 7                      # renders with the same seed match
+/// sampler = "sobol"             # or independent, stratified, halton
+/// adaptive = { min_samples = 16, threshold = 0.01 }
+/// sample_map = "counts.png"     # samples each pixel took, for `render`
+/// filter = "gaussian"           # box (default), tent, mitchell, lanczos,
+///                               # or { type = "gaussian", radius = 2 }
+/// spectral = true               # trace wavelengths, for dispersion
+///
+/// [textures.ground]
+/// type = "checker"
+/// scale = 0.32
+/// even = [0.2, 0.3, 0.1]
+/// odd = [0.9, 0.9, 0.9]
+///
+/// [materials.floor]
+/// type = "lambertian"
+/// albedo = "ground"             # a texture name, [r, g, b], or a table
+///
+/// [materials.prism]
+/// type = "dielectric"
+/// refraction_index = "bk7"      # a number, bk7, diamond, or a table:
+///                               # { type = "cauchy", a = 1.5, b = 0.004 } or
+///                               # { type = "sellmeier", b = [..], c = [..] }
+/// transmittance = [0.9, 0.8, 0.8]   # or absorption, per unit distance
+/// depth = 2                     # the distance transmittance is for
+///
+/// [[entities]]
+/// type = "sphere"
+/// center = [0, -1000, 0]
+/// radius = 1000
+/// material = "floor"            # a material name or an inline table
+/// transform = [{ translate = [0, 1, 0] }, { rotate = [0, 1, 0], degrees = 15 }]
+/// ```
+///
+/// Camera keys mirror the `Camera` fields and default to a 400 pixel wide
+/// 16:9 image looking down -z. Textures are `solid`, `checker`,
+/// `uv_checker`, `image`, `noise`, `turbulence` and `marble`; materials are
+/// `lambertian`, `metal`, `conductor`, `dielectric`, `diffuse_light` and
+/// `isotropic`; entities are `sphere`, `quad`, `triangle`, `box`, `mesh`,
+/// `group` and `constant_medium`. Relative image and mesh paths are
+/// resolved against the scene file's directory, and a mesh's `usemtl` names
+/// refer to the scene's named materials.
+pub fn load(path: impl AsRef<Path>) -> Result<(Camera, World)> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|e| SceneError::Io(path.to_path_buf(), e))?;
+    let table: Table = source
+        .parse()
+        .map_err(|e: toml::de::Error| SceneError::Syntax(path.to_path_buf(), e.to_string()))?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    build(&table, base_dir)
+}
+
+/// Builds a scene from an already parsed TOML document, resolving relative
+/// paths against `base_dir`.
+pub fn build(table: &Table, base_dir: &Path) -> Result<(Camera, World)> {
+    let root = Node {
+        value: None,
+        table: Some(table),
+        path: String::new(),
+    };
+    root.check_keys(&["camera", "textures", "materials", "entities"])?;
+
+    let texture_defs = match root.get("textures")? {
+        Some(node) => node.table()?.iter().collect(),
+        None => HashMap::new(),
+    };
+    let mut scene = SceneBuilder {
+        base_dir,
+        texture_defs,
+        textures: HashMap::new(),
+        resolving: HashSet::new(),
+        materials: HashMap::new(),
+    };
+
+    if let Some(materials) = root.get("materials")? {
+        for (name, value) in materials.table()? {
+            let material = scene.material(&materials.child(name, value))?;
+            scene.materials.insert(name.clone(), material);
+        }
+    }
+    // Named textures are otherwise resolved on first use; this catches
+    // errors in ones nothing refers to.
+    let names: Vec<String> = scene.texture_defs.keys().map(|k| k.to_string()).collect();
+    for name in names {
+        scene.named_texture(&name, &format!("textures.{}", name))?;
+    }
+
+    let camera = match root.get("camera")? {
+        Some(node) => camera(&node)?,
+        None => camera(&Node::empty("camera"))?,
+    };
+
+    let mut world = World::new();
+    if let Some(entities) = root.get("entities")? {
+        for node in entities.array()? {
+            world.add(scene.entity(&node)?);
+        }
+    }
+    Ok((camera, world))
+}
+
+struct SceneBuilder<'a> {
+    base_dir: &'a Path,
+    texture_defs: HashMap<&'a String, &'a Value>,
+    textures: HashMap<String, Texture>,
+    resolving: HashSet<String>,
+    materials: HashMap<String, Material>,
+}
+
+impl SceneBuilder<'_> {
+    fn named_texture(&mut self, name: &str, key: &str) -> Result<Texture> {
+        if let Some(texture) = self.textures.get(name) {
+            return Ok(texture.clone());
+        }
+        let definition = match self.texture_defs.get(&name.to_string()) {
+            Some(definition) => *definition,
+            None => return Err(invalid(key, format!("unknown texture `{}`", name))),
+        };
+        if !self.resolving.insert(name.to_string()) {
+            return Err(invalid(key, format!("texture `{}` refers to itself", name)));
+        }
+        let node = Node::new(definition, format!("textures.{}", name));
+        let texture = self.texture(&node)?;
+        self.resolving.remove(name);
+        self.textures.insert(name.to_string(), texture.clone());
+        Ok(texture)
+    }
+
+    /// A texture is a color array, the name of a texture definition, or an
+    /// inline definition table.
+    fn texture(&mut self, node: &Node) -> Result<Texture> {
+        if let Some(Value::Array(_)) = node.value {
+            return Ok(Texture::Solid(node.vec3()?));
+        }
+        if let Some(Value::String(name)) = node.value {
+            return self.named_texture(name, &node.path);
+        }
+
+        let kind = node.kind()?;
+        match kind.as_str() {
+            "solid" => {
+                node.check_keys(&["type", "color"])?;
+                Ok(Texture::Solid(node.require("color")?.vec3()?))
+            }
+            "checker" | "uv_checker" => {
+                node.check_keys(&["type", "scale", "even", "odd"])?;
+                let scale = node.f32_or("scale", 1.0)?;
+                let even = self.texture(&node.require("even")?)?;
+                let odd = self.texture(&node.require("odd")?)?;
+                Ok(if kind == "checker" {
+                    Texture::checker(scale, even, odd)
+                } else {
+                    Texture::uv_checker(scale, even, odd)
+                })
+            }
+            "image" => {
+                node.check_keys(&["type", "path", "filter", "wrap"])?;
+                let path = node.require("path")?;
+                let image = Image::load(self.base_dir.join(path.str()?))
+                    .map_err(|e| path.error(e.to_string()))?;
+                let filter = match node.get("filter")? {
+                    None => Filter::default(),
+                    Some(filter) => match filter.str()? {
+                        "nearest" => Filter::Nearest,
+                        "bilinear" => Filter::Bilinear,
+                        other => return Err(filter.error(format!("unknown filter `{}`", other))),
+                    },
+                };
+                let wrap = match node.get("wrap")? {
+                    None => Wrap::default(),
+                    Some(wrap) => match wrap.str()? {
+                        "repeat" => Wrap::Repeat,
+                        "clamp" => Wrap::Clamp,
+                        "mirror" => Wrap::Mirror,
+                        other => return Err(wrap.error(format!("unknown wrap mode `{}`", other))),
+                    },
+                };
+                Ok(Texture::Image(Arc::new(image), filter, wrap))
+            }
+            "noise" | "turbulence" | "marble" => {
+                node.check_keys(&["type", "scale", "octaves", "seed"])?;
+                let scale = node.f32_or("scale", 1.0)?;
+                let octaves = node.u32_or("octaves", if kind == "noise" { 1 } else { 7 })?;
+                let seed = node.u64_or("seed", 0)?;
+                Ok(match kind.as_str() {
+                    "noise" => Texture::noise(scale, octaves, seed),
+                    "turbulence" => Texture::turbulence(scale, octaves, seed),
+                    _ => Texture::marble(scale, octaves, seed),
+                })
+            }
+            other => Err(node
+                .require("type")?
+                .error(format!("unknown texture type `{}`", other))),
+        }
+    }
+
+    /// A material is the name of a material definition or an inline
+    /// definition table.
+    fn material(&mut self, node: &Node) -> Result<Material> {
+        if let Some(Value::String(name)) = node.value {
+            return match self.materials.get(name) {
+                Some(material) => Ok(material.clone()),
+                None => Err(node.error(format!("unknown material `{}`", name))),
+            };
+        }
+
+        let kind = node.kind()?;
+        match kind.as_str() {
+            "lambertian" => {
+                node.check_keys(&["type", "albedo"])?;
+                Ok(Material::Lambertian(
+                    self.texture(&node.require("albedo")?)?,
+                ))
+            }
+            "metal" => {
+                node.check_keys(&["type", "albedo", "fuzz"])?;
+                let albedo = self.texture(&node.require("albedo")?)?;
+                Ok(Material::Metal(albedo, node.f32_or("fuzz", 0.0)?))
+            }
+            "conductor" => {
+                node.check_keys(&["type", "preset", "eta", "k", "roughness"])?;
+                let roughness = roughness(node)?;
+                if let Some(preset) = node.get("preset")? {
+                    for key in ["eta", "k"] {
+                        if let Some(extra) = node.get(key)? {
+                            return Err(extra.error("give either a preset or eta and k"));
+                        }
+                    }
+                    return match preset.str()? {
+                        "gold" => Ok(Material::gold(roughness)),
+                        "copper" => Ok(Material::copper(roughness)),
+                        "silver" => Ok(Material::silver(roughness)),
+                        "aluminum" => Ok(Material::aluminum(roughness)),
+                        other => Err(preset.error(format!("unknown conductor `{}`", other))),
+                    };
+                }
+                Ok(Material::conductor(
+                    node.require("eta")?.vec3()?,
+                    node.require("k")?.vec3()?,
+                    roughness,
+                ))
+            }
+            "dielectric" => {
+                node.check_keys(&[
+                    "type",
+                    "refraction_index",
+                    "roughness",
+                    "absorption",
+                    "transmittance",
+                    "depth",
+                ])?;
+                let refraction_index = refraction_index(&node.require("refraction_index")?)?;
+                let absorption = match (node.get("absorption")?, node.get("transmittance")?) {
+                    (Some(_), Some(transmittance)) => {
+                        return Err(transmittance.error("give either absorption or transmittance"));
+                    }
+                    (Some(absorption), None) => {
+                        let a = absorption.vec3()?;
+                        if ![a.x, a.y, a.z].iter().all(|&c| c >= 0.0) {
+                            return Err(absorption.error("absorption can't be negative"));
+                        }
+                        a
+                    }
+                    (None, Some(transmittance)) => {
+                        let depth = node.f32_or("depth", 1.0)?;
+                        if depth <= 0.0 {
+                            return Err(node.require("depth")?.error("depth must be positive"));
+                        }
+                        let t = transmittance.vec3()?;
+                        if ![t.x, t.y, t.z].iter().all(|c| (0.0..=1.0).contains(c)) {
+                            return Err(
+                                transmittance.error("transmittance must be between 0 and 1")
+                            );
+                        }
+                        absorption_coefficient(t, depth)
+                    }
+                    (None, None) => Color::black(),
+                };
+                match roughness(node)? {
+                    roughness if roughness > 0.0 => Ok(Material::RoughDielectric(
+                        refraction_index,
+                        TrowbridgeReitz::from_roughness(roughness),
+                        absorption,
+                    )),
+                    _ => Ok(Material::Dielectric(refraction_index, absorption)),
+                }
+            }
+            "diffuse_light" => {
+                node.check_keys(&["type", "radiance"])?;
+                Ok(Material::DiffuseLight(node.require("radiance")?.vec3()?))
+            }
+            "isotropic" => {
+                node.check_keys(&["type", "albedo"])?;
+                Ok(Material::Isotropic(self.texture(&node.require("albedo")?)?))
+            }
+            other => Err(node
+                .require("type")?
+                .error(format!("unknown material type `{}`", other))),
+        }
+    }
+
+    fn entity(&mut self, node: &Node) -> Result<Entity> {
+        let kind = node.kind()?;
+        let entity = match kind.as_str() {
+            "sphere" => {
+                node.check_keys(&[
+                    "type",
+                    "center",
+                    "center2",
+                    "radius",
+                    "material",
+                    "transform",
+                ])?;
+                let center = node.require("center")?.vec3()?;
+                let radius = node.require("radius")?.f32()?;
+                let material = self.material(&node.require("material")?)?;
+                match node.get("center2")? {
+                    Some(center2) => {
+                        Entity::moving_sphere(center, center2.vec3()?, radius, material)
+                    }
+                    None => Entity::sphere(center, radius, material),
+                }
+            }
+            "quad" => {
+                node.check_keys(&["type", "q", "u", "v", "material", "transform"])?;
+                Entity::quad(
+                    node.require("q")?.vec3()?,
+                    node.require("u")?.vec3()?,
+                    node.require("v")?.vec3()?,
+                    self.material(&node.require("material")?)?,
+                )
+            }
+            "triangle" => {
+                node.check_keys(&["type", "a", "b", "c", "material", "transform"])?;
+                Entity::triangle(
+                    node.require("a")?.vec3()?,
+                    node.require("b")?.vec3()?,
+                    node.require("c")?.vec3()?,
+                    self.material(&node.require("material")?)?,
+                )
+            }
+            "box" => {
+                node.check_keys(&["type", "a", "b", "material", "transform"])?;
+                Entity::cuboid(
+                    node.require("a")?.vec3()?,
+                    node.require("b")?.vec3()?,
+                    self.material(&node.require("material")?)?,
+                )
+            }
+            "mesh" => {
+                node.check_keys(&["type", "path", "material", "transform"])?;
+                let path = node.require("path")?;
+                let default = self.material(&node.require("material")?)?;
+                obj::load(self.base_dir.join(path.str()?), &self.materials, default)
+                    .map_err(|e| path.error(e.to_string()))?
+            }
+            "group" => {
+                node.check_keys(&["type", "entities", "transform"])?;
+                let entities = node
+                    .require("entities")?
+                    .array()?
+                    .iter()
+                    .map(|child| self.entity(child))
+                    .collect::<Result<Vec<_>>>()?;
+                Entity::Group(entities)
+            }
+            "constant_medium" => {
+                node.check_keys(&["type", "boundary", "density", "albedo", "transform"])?;
+                let boundary = self.entity(&node.require("boundary")?)?;
+                let density = node.require("density")?;
+                if density.f32()? <= 0.0 {
+                    return Err(density.error("density must be positive"));
+                }
+                let albedo = self.texture(&node.require("albedo")?)?;
+                Entity::constant_medium(boundary, density.f32()?, albedo)
+            }
+            other => {
+                return Err(node
+                    .require("type")?
+                    .error(format!("unknown entity type `{}`", other)))
+            }
+        };
+
+        match node.get("transform")? {
+            Some(transform) => entity
+                .transformed(self.transform(&transform)?)
+                .ok_or_else(|| transform.error("transform is not invertible")),
+            None => Ok(entity),
+        }
+    }
+
+    /// An array of single-operation tables, applied in order.
+    fn transform(&self, node: &Node) -> Result<Mat4> {
+        let mut m = Mat4::identity();
+        for step in node.array()? {
+            let op = if let Some(offset) = step.get("translate")? {
+                step.check_keys(&["translate"])?;
+                Mat4::translation(offset.vec3()?)
+            } else if let Some(axis) = step.get("rotate")? {
+                step.check_keys(&["rotate", "degrees"])?;
+                let degrees = step.require("degrees")?.f32()?;
+                match axis.vec3()? {
+                    zero if zero.is_near_zero() => {
+                        return Err(axis.error("rotation axis can't be zero"))
+                    }
+                    axis => Mat4::rotation(axis, degrees),
+                }
+            } else if let Some(factors) = step.get("scale")? {
+                step.check_keys(&["scale"])?;
+                match factors.value {
+                    Some(Value::Array(_)) => Mat4::scaling(factors.vec3()?),
+                    _ => {
+                        let s = factors.f32()?;
+                        Mat4::scaling(Vector::new(s, s, s))
+                    }
+                }
+            } else {
+                return Err(step.error("expected one of `translate`, `rotate` or `scale`"));
+            };
+            if op.inverse().is_none() {
+                return Err(step.error("transform is not invertible"));
+            }
+            m = op * m;
+        }
+        // Steps that are each invertible can still multiply out to one
+        // that isn't.
+        if m.inverse().is_none() {
+            return Err(node.error("transform is not invertible"));
+        }
+        Ok(m)
+    }
+}
+
+fn camera(node: &Node) -> Result<Camera> {
+    node.check_keys(&[
+        "image_width",
+        "aspect_ratio",
+        "samples_per_pixel",
+        "max_depth",
+        "russian_roulette_depth",
+        "seed",
+        "sampler",
+        "adaptive",
+        "sample_map",
+        "filter",
+        "spectral",
+        "vfov",
+        "lookfrom",
+        "lookat",
+        "vup",
+        "defocus_angle",
+        "focus_dist",
+        "background",
+        "exposure",
+        "tone_map",
+        "bit_depth",
+        "exr_pixel_type",
+        "exr_compression",
+    ])?;
+
+    let mut c = Camera::new();
+    c.image_width = node.u32_or("image_width", 400)?;
+    c.aspect_ratio = node.f32_or("aspect_ratio", 16.0 / 9.0)?;
+    c.samples_per_pixel = node.u32_or("samples_per_pixel", 100)?;
+    c.max_depth = node.u32_or("max_depth", 50)?;
+    c.russian_roulette_depth = match node.get("russian_roulette_depth")? {
+        Some(depth) if depth.value == Some(&Value::Boolean(false)) => None,
+        Some(depth) => Some(depth.u32()?).filter(|&d| d > 0),
+        None => None,
+    };
+    c.seed = node.u64_or("seed", 0)?;
+    if let Some(sampler) = node.get("sampler")? {
+        c.sampler = match sampler.str()? {
+            "independent" => Sampler::Independent,
+            "stratified" => Sampler::Stratified,
+            "halton" => Sampler::Halton,
+            "sobol" => Sampler::Sobol,
+            other => return Err(sampler.error(format!("unknown sampler `{}`", other))),
+        };
+    }
+    c.vfov = node.f32_or("vfov", 90.0)?;
+    c.lookfrom = node.vec3_or("lookfrom", Point::new(0., 0., 0.))?;
+    c.lookat = node.vec3_or("lookat", Point::new(0., 0., -1.))?;
+    c.vup = node.vec3_or("vup", Vector::new(0., 1., 0.))?;
+    c.defocus_angle = node.f32_or("defocus_angle", 0.0)?;
+    c.focus_dist = node.f32_or("focus_dist", 10.0)?;
+    if let Some(background) = node.get("background")? {
+        c.background = self::background(&background)?;
+    }
+    if let Some(adaptive) = node.get("adaptive")? {
+        adaptive.check_keys(&["min_samples", "threshold"])?;
+        let defaults = AdaptiveSampling::default();
+        c.adaptive = Some(AdaptiveSampling {
+            min_samples: adaptive.u32_or("min_samples", defaults.min_samples)?,
+            threshold: adaptive.f32_or("threshold", defaults.threshold)?,
+        });
+    }
+    if let Some(path) = node.get("sample_map")? {
+        c.sample_map = Some(path.str()?.to_string());
+    }
+    if let Some(filter) = node.get("filter")? {
+        c.filter = pixel_filter(&filter)?;
+    }
+    c.spectral = node.bool_or("spectral", false)?;
+    c.output.exposure = node.f32_or("exposure", 0.0)?;
+    if let Some(tone_map) = node.get("tone_map")? {
+        c.output.tone_map = self::tone_map(&tone_map)?;
+    }
+    if let Some(depth) = node.get("bit_depth")? {
+        c.output.bit_depth = match depth.u32()? {
+            8 => BitDepth::Eight,
+            16 => BitDepth::Sixteen,
+            _ => return Err(depth.error("expected 8 or 16")),
+        };
+    }
+    if let Some(pixel_type) = node.get("exr_pixel_type")? {
+        c.output.exr_pixel_type = match pixel_type.str()? {
+            "half" => exr::PixelType::Half,
+            "float" => exr::PixelType::Float,
+            other => return Err(pixel_type.error(format!("unknown pixel type `{}`", other))),
+        };
+    }
+    if let Some(compression) = node.get("exr_compression")? {
+        c.output.exr_compression = match compression.str()? {
+            "none" => exr::Compression::None,
+            "zip" => exr::Compression::Zip,
+            other => return Err(compression.error(format!("unknown compression `{}`", other))),
+        };
+    }
+
+    if c.image_width == 0 {
+        return Err(node.require("image_width")?.error("must be at least 1"));
+    }
+    if c.samples_per_pixel == 0 {
+        return Err(node
+            .require("samples_per_pixel")?
+            .error("must be at least 1"));
+    }
+    if c.aspect_ratio <= 0.0 {
+        return Err(node.require("aspect_ratio")?.error("must be positive"));
+    }
+    if !(c.vfov > 0.0 && c.vfov < 180.0) {
+        return Err(node
+            .require("vfov")?
+            .error("must be between 0 and 180 degrees"));
+    }
+    let view = c.lookat - c.lookfrom;
+    if view.is_near_zero() {
+        return Err(invalid(
+            &node.key("lookat"),
+            "must differ from `lookfrom`".to_string(),
+        ));
+    }
+    if c.vup.cross(view).magnitude() <= 1e-6 * c.vup.magnitude() * view.magnitude() {
+        return Err(invalid(
+            &node.key("vup"),
+            "must not be parallel to the view direction".to_string(),
+        ));
+    }
+    Ok(c)
+}
+
+fn background(node: &Node) -> Result<Background> {
+    match node.value {
+        Some(Value::Array(_)) => return Ok(Background::Solid(node.vec3()?)),
+        Some(Value::String(name)) => {
+            return match name.as_str() {
+                "black" => Ok(Background::Black),
+                "gradient" => Ok(Background::default()),
+                other => Err(node.error(format!("unknown background `{}`", other))),
+            }
+        }
+        _ => {}
+    }
+
+    match node.kind()?.as_str() {
+        "gradient" => {
+            node.check_keys(&["type", "bottom", "top"])?;
+            Ok(Background::Gradient(
+                node.require("bottom")?.vec3()?,
+                node.require("top")?.vec3()?,
+            ))
+        }
+        "solid" => {
+            node.check_keys(&["type", "color"])?;
+            Ok(Background::Solid(node.require("color")?.vec3()?))
+        }
+        "black" => {
+            node.check_keys(&["type"])?;
+            Ok(Background::Black)
+        }
+        other => Err(node
+            .require("type")?
+            .error(format!("unknown background type `{}`", other))),
+    }
+}
+
+/// Either an operator name or a table, which extended Reinhard needs for
+/// its white point.
+fn tone_map(node: &Node) -> Result<ToneMap> {
+    let kind = match node.value {
+        Some(Value::String(name)) => name.clone(),
+        _ => node.kind()?,
+    };
+    let white = if node.table.is_some() {
+        node.check_keys(&["type", "white"])?;
+        node.get("white")?
+    } else {
+        None
+    };
+    match kind.as_str() {
+        "clamp" => Ok(ToneMap::Clamp),
+        "reinhard" => Ok(ToneMap::Reinhard),
+        "extended_reinhard" => {
+            let Some(w) = white else {
+                return Ok(ToneMap::ExtendedReinhard(4.0));
+            };
+            let value = w.f32()?;
+            if value.is_nan() || value <= 0.0 {
+                return Err(w.error("white must be positive"));
+            }
+            Ok(ToneMap::ExtendedReinhard(value))
+        }
+        "aces" => Ok(ToneMap::Aces),
+        "hable" => Ok(ToneMap::Hable),
+        "agx" => Ok(ToneMap::Agx),
+        other => Err(node.error(format!("unknown tone map `{}`", other))),
+    }
+}
+
+/// A filter is a name, or a table with a `type` and optional `radius`.
+fn pixel_filter(node: &Node) -> Result<PixelFilter> {
+    let kind = match node.value {
+        Some(Value::String(name)) => name.clone(),
+        _ => node.kind()?,
+    };
+    let radius = if node.table.is_some() {
+        node.check_keys(&["type", "radius"])?;
+        node.get("radius")?
+    } else {
+        None
+    };
+    let radius = |default: f32| radius.as_ref().map_or(Ok(default), |r| r.f32());
+    let filter = match kind.as_str() {
+        "box" => PixelFilter::Box(radius(0.5)?),
+        "tent" => PixelFilter::Tent(radius(1.0)?),
+        "gaussian" => PixelFilter::Gaussian(radius(1.5)?),
+        "mitchell" => PixelFilter::Mitchell(radius(2.0)?),
+        "lanczos" => PixelFilter::Lanczos(radius(3.0)?),
+        other => return Err(node.error(format!("unknown filter `{}`", other))),
+    };
+    let radius = filter.radius();
+    if !(radius > 0.0 && radius.is_finite()) {
+        return Err(node.error("radius must be positive"));
+    }
+    Ok(filter)
+}
+
+/// A material's perceptual roughness, from 0 (smooth, the default) to 1.
+fn roughness(node: &Node) -> Result<f32> {
+    let roughness = node.f32_or("roughness", 0.0)?;
+    if !(0.0..=1.0).contains(&roughness) {
+        return Err(node
+            .require("roughness")?
+            .error("roughness must be between 0 and 1"));
+    }
+    Ok(roughness)
+}
+
+/// A number, a named glass, or a dispersion formula's coefficients.
+fn refraction_index(node: &Node) -> Result<RefractionIndex> {
+    let index = match node.value {
+        Some(Value::Float(_) | Value::Integer(_)) => RefractionIndex::Constant(node.f32()?),
+        Some(Value::String(name)) => match name.as_str() {
+            "bk7" => RefractionIndex::bk7(),
+            "diamond" => RefractionIndex::diamond(),
+            other => return Err(node.error(format!("unknown glass `{}`", other))),
+        },
+        _ => dispersion_formula(node)?,
+    };
+    let (lo, hi) = (LAMBDA_MIN as u32, LAMBDA_MAX as u32);
+    if let Some(nm) = (lo..=hi).find(|&nm| {
+        let n = index.at(Some(nm as f32));
+        !(n > 0.0 && n.is_finite())
+    }) {
+        return Err(node.error(format!("the index isn't a positive number at {} nm", nm)));
+    }
+    Ok(index)
+}
+
+/// A `cauchy` or `sellmeier` table of coefficients.
+fn dispersion_formula(node: &Node) -> Result<RefractionIndex> {
+    let coefficients = |key: &str| -> Result<[f32; 3]> {
+        let v = node.require(key)?.vec3()?;
+        Ok([v.x, v.y, v.z])
+    };
+    match node.kind()?.as_str() {
+        "cauchy" => {
+            node.check_keys(&["type", "a", "b"])?;
+            Ok(RefractionIndex::Cauchy(
+                node.require("a")?.f32()?,
+                node.require("b")?.f32()?,
+            ))
+        }
+        "sellmeier" => {
+            node.check_keys(&["type", "b", "c"])?;
+            let c = coefficients("c")?;
+            // Each C is the square of a wavelength, in μm, where the index
+            // has a pole.
+            let (lo, hi) = ((LAMBDA_MIN / 1000.0).powi(2), (LAMBDA_MAX / 1000.0).powi(2));
+            if let Some(pole) = c.iter().find(|c| (lo..=hi).contains(*c)) {
+                return Err(node.require("c")?.error(format!(
+                    "the index has a pole at {:.0} nm, in the traced range",
+                    pole.sqrt() * 1000.0
+                )));
+            }
+            Ok(RefractionIndex::Sellmeier(coefficients("b")?, c))
+        }
+        other => Err(node.error(format!("unknown refraction index type `{}`", other))),
+    }
+}
+
+fn invalid(key: &str, message: String) -> SceneError {
+    SceneError::Invalid {
+        key: key.to_string(),
+        message,
+    }
+}
+
+/// A value in the scene document along with the key path that led to it,
+/// so every error can point at its source.
+struct Node<'a> {
+    value: Option<&'a Value>,
+    table: Option<&'a Table>,
+    path: String,
+}
+
+impl<'a> Node<'a> {
+    fn new(value: &'a Value, path: String) -> Self {
+        Self {
+            value: Some(value),
+            table: value.as_table(),
+            path,
+        }
+    }
+
+    /// Stands in for an omitted table so its keys all take defaults.
+    fn empty(path: &str) -> Self {
+        Self {
+            value: None,
+            table: None,
+            path: path.to_string(),
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", self.path, key)
+        }
+    }
+
+    fn child(&self, key: &str, value: &'a Value) -> Node<'a> {
+        Node::new(value, self.key(key))
+    }
+
+    fn error(&self, message: impl Into<String>) -> SceneError {
+        invalid(&self.path, message.into())
+    }
+
+    fn type_error(&self, expected: &str) -> SceneError {
+        let found = match self.value {
+            Some(value) => value.type_str(),
+            None => "nothing",
+        };
+        self.error(format!("expected {}, found {}", expected, found))
+    }
+
+    fn table(&self) -> Result<&'a Table> {
+        self.table.ok_or_else(|| self.type_error("a table"))
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Node<'a>>> {
+        if self.value.is_none() && self.table.is_none() {
+            return Ok(None);
+        }
+        Ok(self.table()?.get(key).map(|value| self.child(key, value)))
+    }
+
+    fn require(&self, key: &str) -> Result<Node<'a>> {
+        self.get(key)?
+            .ok_or_else(|| invalid(&self.key(key), "missing required key".to_string()))
+    }
+
+    fn check_keys(&self, allowed: &[&str]) -> Result<()> {
+        if self.value.is_none() && self.table.is_none() {
+            return Ok(());
+        }
+        match self
+            .table()?
+            .keys()
+            .find(|k| !allowed.contains(&k.as_str()))
+        {
+            Some(key) => Err(invalid(&self.key(key), "unknown key".to_string())),
+            None => Ok(()),
+        }
+    }
+
+    fn kind(&self) -> Result<String> {
+        self.table()?;
+        Ok(self.require("type")?.str()?.to_string())
+    }
+
+    fn str(&self) -> Result<&'a str> {
+        match self.value {
+            Some(Value::String(s)) => Ok(s),
+            _ => Err(self.type_error("a string")),
+        }
+    }
+
+    fn bool(&self) -> Result<bool> {
+        match self.value {
+            Some(Value::Boolean(b)) => Ok(*b),
+            _ => Err(self.type_error("a boolean")),
+        }
+    }
+
+    fn f32(&self) -> Result<f32> {
+        match self.value {
+            Some(Value::Float(f)) => Ok(*f as f32),
+            Some(Value::Integer(i)) => Ok(*i as f32),
+            _ => Err(self.type_error("a number")),
+        }
+    }
+
+    fn u32(&self) -> Result<u32> {
+        match self.value {
+            Some(Value::Integer(i)) => {
+                u32::try_from(*i).map_err(|_| self.error("expected a non-negative integer"))
+            }
+            _ => Err(self.type_error("an integer")),
+        }
+    }
+
+    fn u64(&self) -> Result<u64> {
+        match self.value {
+            Some(Value::Integer(i)) => {
+                u64::try_from(*i).map_err(|_| self.error("expected a non-negative integer"))
+            }
+            _ => Err(self.type_error("an integer")),
+        }
+    }
+
+    fn vec3(&self) -> Result<Vector> {
+        let items = self.array()?;
+        if items.len() != 3 {
+            return Err(self.error(format!(
+                "expected an array of 3 numbers, found {} items",
+                items.len()
+            )));
+        }
+        Ok(Vector::new(
+            items[0].f32()?,
+            items[1].f32()?,
+            items[2].f32()?,
+        ))
+    }
+
+    fn array(&self) -> Result<Vec<Node<'a>>> {
+        match self.value {
+            Some(Value::Array(items)) => Ok(items
+                .iter()
+                .enumerate()
+                .map(|(i, value)| Node::new(value, format!("{}[{}]", self.path, i)))
+                .collect()),
+            _ => Err(self.type_error("an array")),
+        }
+    }
+
+    fn f32_or(&self, key: &str, default: f32) -> Result<f32> {
+        self.get(key)?.map_or(Ok(default), |n| n.f32())
+    }
+
+    fn bool_or(&self, key: &str, default: bool) -> Result<bool> {
+        self.get(key)?.map_or(Ok(default), |n| n.bool())
+    }
+
+    fn u32_or(&self, key: &str, default: u32) -> Result<u32> {
+        self.get(key)?.map_or(Ok(default), |n| n.u32())
+    }
+
+    fn u64_or(&self, key: &str, default: u64) -> Result<u64> {
+        self.get(key)?.map_or(Ok(default), |n| n.u64())
+    }
+
+    fn vec3_or(&self, key: &str, default: Vector) -> Result<Vector> {
+        self.get(key)?.map_or(Ok(default), |n| n.vec3())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<(Camera, World)> {
+        let table: Table = source.parse().unwrap();
+        build(&table, Path::new("."))
+    }
+
+    fn error_key(source: &str) -> String {
+        match parse(source) {
+            Err(SceneError::Invalid { key, .. }) => key,
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    fn parse_camera(source: &str) -> Camera {
+        parse(source).unwrap().0
+    }
+
+    #[test]
+    fn russian_roulette_depth() {
+        assert_eq!(parse_camera("").russian_roulette_depth, None);
+        let depth = |value: &str| {
+            parse_camera(&format!("[camera]\nrussian_roulette_depth = {}\n", value))
+                .russian_roulette_depth
+        };
+        assert_eq!(depth("5"), Some(5));
+        assert_eq!(depth("0"), None);
+        assert_eq!(depth("false"), None);
+        assert_eq!(
+            error_key("[camera]\nrussian_roulette_depth = true\n"),
+            "camera.russian_roulette_depth"
+        );
+    }
+
+    #[test]
+    fn seed() {
+        assert_eq!(parse_camera("").seed, 0);
+        assert_eq!(
+            parse_camera("[camera]\nseed = 9007199254740993\n").seed,
+            9_007_199_254_740_993
+        );
+        assert_eq!(error_key("[camera]\nseed = -1\n"), "camera.seed");
+    }
+
+    #[test]
+    fn sampler() {
+        assert_eq!(parse_camera("").sampler, Sampler::Sobol);
+        assert_eq!(
+            parse_camera("[camera]\nsampler = \"halton\"\n").sampler,
+            Sampler::Halton
+        );
+        assert_eq!(
+            error_key("[camera]\nsampler = \"random\"\n"),
+            "camera.sampler"
+        );
+    }
+
+    #[test]
+    fn adaptive_sampling() {
+        assert!(parse_camera("").adaptive.is_none());
+        let camera =
+            parse_camera("[camera]\nadaptive = { min_samples = 8 }\nsample_map = \"counts.png\"\n");
+        let adaptive = camera.adaptive.unwrap();
+        assert_eq!((adaptive.min_samples, adaptive.threshold), (8, 0.01));
+        assert_eq!(camera.sample_map.as_deref(), Some("counts.png"));
+        assert_eq!(
+            error_key("[camera]\nadaptive = { samples = 8 }\n"),
+            "camera.adaptive.samples"
+        );
+    }
+
+    #[test]
+    fn pixel_filter() {
+        let filter = |value: &str| parse_camera(&format!("[camera]\nfilter = {}\n", value)).filter;
+        assert_eq!(parse_camera("").filter, PixelFilter::Box(0.5));
+        assert_eq!(filter("\"tent\""), PixelFilter::Tent(1.0));
+        assert_eq!(
+            filter("{ type = \"mitchell\", radius = 1.5 }"),
+            PixelFilter::Mitchell(1.5)
+        );
+        for radius in ["0", "-1", "nan", "inf"] {
+            assert_eq!(
+                error_key(&format!(
+                    "[camera]\nfilter = {{ type = \"lanczos\", radius = {} }}\n",
+                    radius
+                )),
+                "camera.filter"
+            );
+        }
+    }
+
+    /// The material of a lone sphere made of `source`.
+    fn material(source: &str) -> Material {
+        let world = parse(&format!(
+            "[[entities]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = 1\nmaterial = {}\n",
+            source
+        ))
+        .unwrap()
+        .1;
+        match &world.entities()[0] {
+            Entity::Sphere(_, _, material) => material.clone(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn conductors() {
+        assert!(matches!(
+            material("{ type = \"conductor\", preset = \"gold\", roughness = 0.3 }"),
+            Material::Conductor(..)
+        ));
+        assert!(matches!(
+            material("{ type = \"conductor\", eta = [0.2, 0.9, 1.1], k = [3.9, 2.4, 2.2] }"),
+            Material::Conductor(..)
+        ));
+        assert_eq!(
+            error_key("[materials.m]\ntype = \"conductor\"\npreset = \"gold\"\nk = [1, 1, 1]\n"),
+            "materials.m.k"
+        );
+        assert_eq!(
+            error_key("[materials.m]\ntype = \"conductor\"\npreset = \"brass\"\n"),
+            "materials.m.preset"
+        );
+    }
+
+    #[test]
+    fn roughness() {
+        assert!(matches!(
+            material("{ type = \"dielectric\", refraction_index = 1.5, roughness = 0.2 }"),
+            Material::RoughDielectric(..)
+        ));
+        assert!(matches!(
+            material("{ type = \"dielectric\", refraction_index = 1.5, roughness = 0 }"),
+            Material::Dielectric(..)
+        ));
+        for kind in [
+            "type = \"dielectric\"\nrefraction_index = 1.5",
+            "preset = \"gold\"\ntype = \"conductor\"",
+        ] {
+            for roughness in ["-0.1", "1.5", "nan"] {
+                assert_eq!(
+                    error_key(&format!(
+                        "[materials.m]\n{}\nroughness = {}\n",
+                        kind, roughness
+                    )),
+                    "materials.m.roughness"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn dielectric_absorption() {
+        let interior = |source: &str| material(source).interior().unwrap();
+        assert_eq!(
+            interior("{ type = \"dielectric\", refraction_index = 1.5 }"),
+            Color::black()
+        );
+        assert_eq!(
+            interior("{ type = \"dielectric\", refraction_index = 1.5, absorption = [0, 1, 2] }"),
+            Color::new(0., 1., 2.)
+        );
+        let a = interior(
+            "{ type = \"dielectric\", refraction_index = 1.5, transmittance = [0.8, 0.9, 1], depth = 0.5 }",
+        );
+        assert!((a - absorption_coefficient(Color::new(0.8, 0.9, 1.), 0.5)).magnitude() < 1e-6);
+
+        let dielectric = "[materials.m]\ntype = \"dielectric\"\nrefraction_index = 1.5\n";
+        for (key, value) in [
+            ("absorption", "[0, -1, 0]"),
+            ("absorption", "[0, nan, 0]"),
+            ("transmittance", "[0.5, 1.5, 0.5]"),
+            ("transmittance", "[-0.5, 0.5, 0.5]"),
+        ] {
+            assert_eq!(
+                error_key(&format!("{}{} = {}\n", dielectric, key, value)),
+                format!("materials.m.{}", key)
+            );
+        }
+        assert_eq!(
+            error_key(&format!(
+                "{}transmittance = [1, 1, 1]\ndepth = 0\n",
+                dielectric
+            )),
+            "materials.m.depth"
+        );
+    }
+
+    #[test]
+    fn dispersion() {
+        assert!(!parse_camera("").spectral);
+        assert!(parse_camera("[camera]\nspectral = true\n").spectral);
+        let index = |source: &str| match material(&format!(
+            "{{ type = \"dielectric\", refraction_index = {} }}",
+            source
+        )) {
+            Material::Dielectric(index, _) => index,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            index("{ type = \"cauchy\", a = 1.5, b = 0.004 }"),
+            RefractionIndex::Cauchy(1.5, 0.004)
+        );
+        assert_eq!(index("\"bk7\""), RefractionIndex::bk7());
+        assert_eq!(
+            index("{ type = \"sellmeier\", b = [1.04, 0.23, 1.01], c = [0.006, 0.02, 103.56] }"),
+            RefractionIndex::Sellmeier([1.04, 0.23, 1.01], [0.006, 0.02, 103.56])
+        );
+
+        let sellmeier = |b: &str, c: &str| {
+            error_key(&format!(
+                "[materials.m]\ntype = \"dielectric\"\n\
+                 refraction_index = {{ type = \"sellmeier\", b = {}, c = {} }}\n",
+                b, c
+            ))
+        };
+        // A pole at 500 nm.
+        assert_eq!(
+            sellmeier("[1, 0, 0]", "[0.25, 0, 0]"),
+            "materials.m.refraction_index.c"
+        );
+        // n² below zero across the range.
+        assert_eq!(
+            sellmeier("[-2, 0, 0]", "[0, 0, 0]"),
+            "materials.m.refraction_index"
+        );
+        for index in [
+            "0",
+            "-1.5",
+            "nan",
+            "inf",
+            "{ type = \"cauchy\", a = -1.5, b = 0.004 }",
+            "{ type = \"cauchy\", a = 1.5, b = -1 }",
+        ] {
+            assert_eq!(
+                error_key(&format!(
+                    "[materials.m]\ntype = \"dielectric\"\nrefraction_index = {}\n",
+                    index
+                )),
+                "materials.m.refraction_index"
+            );
+        }
+    }
+
+    #[test]
+    fn builds_scene() {
+        let (camera, world) = parse(
+            r#"
+            [camera]
+            image_width = 200
+            lookfrom = [0, 1, 5]
+            background = "black"
+
+            [textures.check]
+            type = "checker"
+            scale = 0.5
+            even = "white"
+            odd = [0, 0, 0]
+
+            [textures.white]
+            type = "solid"
+            color = [1, 1, 1]
+
+            [materials.floor]
+            type = "lambertian"
+            albedo = "check"
+
+            [[entities]]
+            type = "sphere"
+            center = [0, -100, 0]
+            radius = 100
+            material = "floor"
+
+            [[entities]]
+            type = "box"
+            a = [0, 0, 0]
+            b = [1, 1, 1]
+            material = { type = "metal", albedo = [0.8, 0.8, 0.8], fuzz = 0.1 }
+            transform = [{ rotate = [0, 1, 0], degrees = 30 }, { translate = [1, 0, 0] }]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(camera.image_width, 200);
+        assert_eq!(camera.samples_per_pixel, 100);
+        assert_eq!(camera.lookfrom, Point::new(0., 1., 5.));
+        assert!(matches!(camera.background, Background::Black));
+        assert_eq!(world.entities().len(), 2);
+        assert!(matches!(world.entities()[1], Entity::Instance(_)));
+    }
+
+    #[test]
+    fn reports_offending_keys() {
+        assert_eq!(
+            error_key("[camera]\nimage_width = 1.5\n"),
+            "camera.image_width"
+        );
+        assert_eq!(error_key("[camera]\nfov = 20\n"), "camera.fov");
+        assert_eq!(
+            error_key(
+                "[[entities]]\ntype = \"sphere\"\ncenter = [0, 0]\nradius = 1\nmaterial = \"x\"\n"
+            ),
+            "entities[0].center"
+        );
+        assert_eq!(
+            error_key("[[entities]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = 1\nmaterial = \"x\"\n"),
+            "entities[0].material"
+        );
+        assert_eq!(
+            error_key("[[entities]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nmaterial = \"x\"\n"),
+            "entities[0].radius"
+        );
+        assert_eq!(
+            error_key("[materials.m]\ntype = \"lambertian\"\nalbedo = [1, \"red\", 0]\n"),
+            "materials.m.albedo[1]"
+        );
+        assert_eq!(
+            error_key("[[entities]]\ntype = \"cone\"\n"),
+            "entities[0].type"
+        );
+        let sphere = "[[entities]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = 1\nmaterial = { type = \"lambertian\", albedo = [1, 1, 1] }\n";
+        assert_eq!(
+            error_key(&format!("{}transform = [{{ scale = 0 }}]\n", sphere)),
+            "entities[0].transform[0]"
+        );
+        assert_eq!(
+            error_key(&format!(
+                "{}transform = [{{ scale = 1e-7 }}, {{ scale = 1e-7 }}]\n",
+                sphere
+            )),
+            "entities[0].transform"
+        );
+        assert_eq!(
+            error_key(&format!(
+                "{}transform = [{{ rotate = [0, 0, 0], degrees = 30 }}]\n",
+                sphere
+            )),
+            "entities[0].transform[0].rotate"
+        );
+        for vfov in ["0", "180", "-10", "nan"] {
+            assert_eq!(
+                error_key(&format!("[camera]\nvfov = {}\n", vfov)),
+                "camera.vfov"
+            );
+        }
+        assert_eq!(
+            error_key("[camera]\nlookfrom = [0, 5, 0]\nlookat = [0, 0, 0]\n"),
+            "camera.vup"
+        );
+        assert_eq!(error_key("[camera]\nvup = [0, 0, 0]\n"), "camera.vup");
+        assert_eq!(
+            error_key("[camera]\ntone_map = { type = \"extended_reinhard\", white = 0 }\n"),
+            "camera.tone_map.white"
+        );
+        assert_eq!(
+            error_key("[materials.m]\ntype = \"dielectric\"\nrefraction_index = 1.5\nabsorption = [1, 1, 1]\ntransmittance = [1, 1, 1]\n"),
+            "materials.m.transmittance"
+        );
+        assert_eq!(
+            error_key("[materials.m]\ntype = \"dielectric\"\nrefraction_index = \"flint\"\n"),
+            "materials.m.refraction_index"
+        );
+    }
+
+    #[test]
+    fn rejects_texture_cycles() {
+        let key = error_key(
+            "[textures.a]\ntype = \"checker\"\neven = \"b\"\nodd = [0, 0, 0]\n\
+             [textures.b]\ntype = \"checker\"\neven = \"a\"\nodd = [0, 0, 0]\n",
+        );
+
+        assert!(key == "textures.a.even" || key == "textures.b.even");
+    }
+}
@@ -0,0 +1,212 @@
+use crate::color::Color;
+
+/// The range of wavelengths traced, in nm.
+pub const LAMBDA_MIN: f32 = 380.0;
+pub const LAMBDA_MAX: f32 = 720.0;
+
+/// The integral of the CIE ȳ curve over the traced range, so that a flat
+/// spectrum of 1 has luminance 1.
+const CIE_Y_INTEGRAL: f32 = 106.911_87;
+
+/// CIE XYZ to linear sRGB, adapted (Bradford) from the white of a flat
+/// spectrum to D65. A flat spectrum is what RGB white upsamples to, so
+/// white stays white.
+const XYZ_TO_RGB: [[f32; 3]; 3] = [
+    [3.147_639_4, -1.663_023_7, -0.480_554_4],
+    [-0.994_778, 1.953_673_1, 0.039_730_6],
+    [0.063_538_3, -0.214_545, 1.152_029],
+];
+
+/// The wavelengths a path is traced at, one per channel of the colors it
+/// carries. The first is the hero wavelength; the others are spaced evenly
+/// from it across the range (Wilkie et al. 2014), so one random number
+/// covers the spectrum.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wavelengths {
+    pub lambda: [f32; 3],
+    pdf: [f32; 3],
+}
+
+impl Wavelengths {
+    pub fn sample(u: f32) -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let lambda = [0.0, 1.0, 2.0].map(|i: f32| LAMBDA_MIN + (u + i / 3.0).fract() * range);
+        Self {
+            lambda,
+            pdf: [1.0 / range; 3],
+        }
+    }
+
+    pub fn hero(&self) -> f32 {
+        self.lambda[0]
+    }
+
+    /// Follows only the hero wavelength from here on, as when light
+    /// disperses and each wavelength would take its own direction. The
+    /// others no longer count toward the result, even for what they've
+    /// already gathered.
+    pub fn terminate_secondary(&mut self) {
+        if self.is_terminated() {
+            return;
+        }
+        self.pdf = [self.pdf[0] / 3.0, 0.0, 0.0];
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.pdf[1] == 0.0
+    }
+
+    /// The spectrum an RGB color stands for, at each wavelength.
+    pub fn upsample(&self, rgb: Color) -> Color {
+        let [a, b, c] = self.lambda.map(|lambda| smits(rgb, lambda));
+        Color::new(a, b, c)
+    }
+
+    /// Linear sRGB for `radiance` sampled at these wavelengths.
+    pub fn to_rgb(&self, radiance: Color) -> Color {
+        let values = [radiance.x, radiance.y, radiance.z];
+        let mut xyz = Color::black();
+        for ((&lambda, &pdf), value) in self.lambda.iter().zip(&self.pdf).zip(values) {
+            if pdf != 0.0 {
+                xyz = xyz + cie_xyz(lambda) * (value / pdf);
+            }
+        }
+        xyz_to_rgb(xyz / (3.0 * CIE_Y_INTEGRAL))
+    }
+}
+
+pub fn xyz_to_rgb(xyz: Color) -> Color {
+    let row = |r: [f32; 3]| r[0] * xyz.x + r[1] * xyz.y + r[2] * xyz.z;
+    Color::new(row(XYZ_TO_RGB[0]), row(XYZ_TO_RGB[1]), row(XYZ_TO_RGB[2]))
+}
+
+/// The CIE 1931 color matching functions, by the multi-lobe Gaussian fit
+/// of Wyman, Sloan and Shirley (2013).
+pub fn cie_xyz(lambda: f32) -> Color {
+    let g = |mu: f32, below: f32, above: f32| {
+        let t = (lambda - mu) / if lambda < mu { below } else { above };
+        (-0.5 * t * t).exp()
+    };
+    Color::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+// Smits' (1999) basis spectra, in ten bins across the range.
+const WHITE: [f32; 10] = [1.0, 1.0, 0.9999, 0.9993, 0.9992, 0.9998, 1.0, 1.0, 1.0, 1.0];
+const CYAN: [f32; 10] = [
+    0.971, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0, 0.0, 0.0,
+];
+const MAGENTA: [f32; 10] = [
+    1.0, 1.0, 0.9685, 0.2229, 0.0, 0.0458, 0.8369, 1.0, 1.0, 0.9959,
+];
+const YELLOW: [f32; 10] = [
+    0.0001, 0.0, 0.1088, 0.6651, 1.0, 1.0, 0.9996, 0.9586, 0.9685, 0.984,
+];
+const RED: [f32; 10] = [
+    0.1012, 0.0515, 0.0, 0.0, 0.0, 0.0, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const GREEN: [f32; 10] = [
+    0.0, 0.0, 0.0273, 0.7937, 1.0, 0.9418, 0.1719, 0.0, 0.0, 0.0025,
+];
+const BLUE: [f32; 10] = [
+    1.0, 1.0, 0.8916, 0.3323, 0.0, 0.0, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// A smooth spectrum for `rgb` at `lambda`: as much white as all three
+/// channels share, then as much of one secondary as the top two share, then
+/// the rest of the primary.
+fn smits(rgb: Color, lambda: f32) -> f32 {
+    let basis = |table: &[f32; 10]| {
+        // Linear between bin centers, flat past the outermost ones.
+        let x = ((lambda - LAMBDA_MIN) / (LAMBDA_MAX - LAMBDA_MIN) * 10.0 - 0.5).clamp(0.0, 9.0);
+        let i = (x as usize).min(8);
+        let t = x - i as f32;
+        table[i] * (1.0 - t) + table[i + 1] * t
+    };
+    let (r, g, b) = (rgb.x, rgb.y, rgb.z);
+    if r <= g && r <= b {
+        r * basis(&WHITE)
+            + if g <= b {
+                (g - r) * basis(&CYAN) + (b - g) * basis(&BLUE)
+            } else {
+                (b - r) * basis(&CYAN) + (g - b) * basis(&GREEN)
+            }
+    } else if g <= r && g <= b {
+        g * basis(&WHITE)
+            + if r <= b {
+                (r - g) * basis(&MAGENTA) + (b - r) * basis(&BLUE)
+            } else {
+                (b - g) * basis(&MAGENTA) + (r - b) * basis(&RED)
+            }
+    } else {
+        b * basis(&WHITE)
+            + if r <= g {
+                (r - b) * basis(&YELLOW) + (g - r) * basis(&GREEN)
+            } else {
+                (g - b) * basis(&YELLOW) + (r - g) * basis(&RED)
+            }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The average color of many samples of `rgb`'s spectrum.
+    fn round_trip(rgb: Color, terminate: bool) -> Color {
+        let n = 10_000;
+        let total = (0..n).fold(Color::black(), |total, i| {
+            let mut lambda = Wavelengths::sample((i as f32 + 0.5) / n as f32);
+            if terminate {
+                lambda.terminate_secondary();
+            }
+            total + lambda.to_rgb(lambda.upsample(rgb))
+        });
+        total / n as f32
+    }
+
+    #[test]
+    fn hero_wavelengths() {
+        let lambda = Wavelengths::sample(0.9);
+        assert!((lambda.hero() - 686.0).abs() < 1e-3);
+        assert!((lambda.lambda[1] - 459.3333).abs() < 1e-3);
+        assert!((lambda.lambda[2] - 572.6667).abs() < 1e-3);
+        assert!(lambda
+            .lambda
+            .iter()
+            .all(|l| (LAMBDA_MIN..LAMBDA_MAX).contains(l)));
+    }
+
+    #[test]
+    fn white_stays_white() {
+        for terminate in [false, true] {
+            let white = round_trip(Color::new(2.0, 2.0, 2.0), terminate);
+            assert!(
+                (white - Color::new(2.0, 2.0, 2.0)).magnitude() < 1e-2,
+                "{:?}",
+                white
+            );
+        }
+    }
+
+    #[test]
+    fn colors_survive_upsampling() {
+        for rgb in [
+            Color::new(0.8, 0.2, 0.1),
+            Color::new(0.1, 0.5, 0.3),
+            Color::new(0.2, 0.3, 0.9),
+            Color::new(0.65, 0.05, 0.05),
+        ] {
+            let back = round_trip(rgb, false);
+            assert!(
+                (back - rgb).magnitude() < 0.06,
+                "{:?} became {:?}",
+                rgb,
+                back
+            );
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use crate::{
+    color::Color,
+    image::{Filter, Image, Wrap},
+    perlin::Perlin,
+    vec3::Point,
+};
+
+#[derive(Clone)]
+pub enum Texture {
+    Solid(Color),
+    /// A 3D checkerboard of cubes with the given edge length, alternating
+    /// between the two textures throughout space.
+    Checker(f32, Box<Texture>, Box<Texture>),
+    /// A checkerboard laid out in the surface's UV space, with squares of
+    /// the given size in texture coordinates.
+    UvChecker(f32, Box<Texture>, Box<Texture>),
+    Image(Arc<Image>, Filter, Wrap),
+    /// Fractal Brownian motion with the given frequency and octave count,
+    /// mapped from [-1, 1] to gray levels.
+    Noise(Arc<Perlin>, f32, u32),
+    Turbulence(Arc<Perlin>, f32, u32),
+    /// Sine bands along z, perturbed by turbulence of the given octave count.
+    Marble(Arc<Perlin>, f32, u32),
+}
+
+impl Texture {
+    pub fn checker(scale: f32, even: Texture, odd: Texture) -> Self {
+        Texture::Checker(scale, Box::new(even), Box::new(odd))
+    }
+
+    pub fn uv_checker(scale: f32, even: Texture, odd: Texture) -> Self {
+        Texture::UvChecker(scale, Box::new(even), Box::new(odd))
+    }
+
+    pub fn image(image: Image) -> Self {
+        Texture::Image(Arc::new(image), Filter::default(), Wrap::default())
+    }
+
+    pub fn noise(scale: f32, octaves: u32, seed: u64) -> Self {
+        Texture::Noise(Arc::new(Perlin::new(seed)), scale, octaves)
+    }
+
+    pub fn turbulence(scale: f32, octaves: u32, seed: u64) -> Self {
+        Texture::Turbulence(Arc::new(Perlin::new(seed)), scale, octaves)
+    }
+
+    pub fn marble(scale: f32, octaves: u32, seed: u64) -> Self {
+        Texture::Marble(Arc::new(Perlin::new(seed)), scale, octaves)
+    }
+
+    pub fn value(&self, u: f32, v: f32, p: Point) -> Color {
+        match self {
+            Texture::Solid(color) => *color,
+            Texture::Checker(scale, even, odd) => {
+                let cell = |x: f32| (x / scale).floor() as i64;
+                if (cell(p.x) + cell(p.y) + cell(p.z)) % 2 == 0 {
+                    even.value(u, v, p)
+                } else {
+                    odd.value(u, v, p)
+                }
+            }
+            Texture::UvChecker(scale, even, odd) => {
+                let cell = |x: f32| (x / scale).floor() as i64;
+                if (cell(u) + cell(v)) % 2 == 0 {
+                    even.value(u, v, p)
+                } else {
+                    odd.value(u, v, p)
+                }
+            }
+            Texture::Image(image, filter, wrap) => image.sample(u, v, *filter, *wrap),
+            Texture::Noise(perlin, scale, octaves) => {
+                let n = perlin.fbm(p * *scale, *octaves, 2.0, 0.5);
+                Color::white() * (0.5 * (1.0 + n)).clamp(0.0, 1.0)
+            }
+            Texture::Turbulence(perlin, scale, octaves) => {
+                Color::white() * perlin.turbulence(p * *scale, *octaves).min(1.0)
+            }
+            Texture::Marble(perlin, scale, octaves) => {
+                let phase = *scale * p.z + 10.0 * perlin.turbulence(p, *octaves);
+                Color::white() * 0.5 * (1.0 + phase.sin())
+            }
+        }
+    }
+}
+
+impl From<Color> for Texture {
+    fn from(color: Color) -> Self {
+        Texture::Solid(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checker() {
+        let t = Texture::checker(2.0, Color::white().into(), Color::black().into());
+
+        assert_eq!(t.value(0., 0., Point::new(0.5, 0.5, 0.5)), Color::white());
+        assert_eq!(t.value(0., 0., Point::new(2.5, 0.5, 0.5)), Color::black());
+        assert_eq!(t.value(0., 0., Point::new(2.5, -0.5, 0.5)), Color::white());
+        assert_eq!(
+            t.value(0., 0., Point::new(-0.5, -0.5, -0.5)),
+            Color::black()
+        );
+    }
+
+    #[test]
+    fn uv_checker() {
+        let t = Texture::uv_checker(0.5, Color::white().into(), Color::black().into());
+        let p = Point::new(0., 0., 0.);
+
+        assert_eq!(t.value(0.25, 0.25, p), Color::white());
+        assert_eq!(t.value(0.75, 0.25, p), Color::black());
+        assert_eq!(t.value(0.75, 0.75, p), Color::white());
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{color::Color, vec3::Vec3};
+
+/// Maps scene radiance onto the [0, 1] range of a display.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ToneMap {
+    /// Clips each channel, so anything brighter than 1 blows out.
+    #[default]
+    Clamp,
+    /// L / (1 + L) on luminance, which never quite reaches white.
+    Reinhard,
+    /// Reinhard rescaled so that luminance at the given white point maps
+    /// to exactly 1.
+    ExtendedReinhard(f32),
+    /// Stephen Hill's fit of the ACES reference and output transforms.
+    Aces,
+    /// John Hable's filmic curve from Uncharted 2.
+    Hable,
+    /// Troy Sobotka's AgX, via Benjamin Wrensch's polynomial fit of its
+    /// default contrast curve.
+    Agx,
+}
+
+impl ToneMap {
+    /// Maps linear radiance to linear display values in [0, 1].
+    pub fn apply(self, color: Color) -> Color {
+        let color = Color::new(color.x.max(0.0), color.y.max(0.0), color.z.max(0.0));
+        let mapped = match self {
+            ToneMap::Clamp => color,
+            ToneMap::Reinhard => scale_luminance(color, |l| l / (1.0 + l)),
+            ToneMap::ExtendedReinhard(white) => {
+                scale_luminance(color, |l| l * (1.0 + l / (white * white)) / (1.0 + l))
+            }
+            ToneMap::Aces => aces(color),
+            ToneMap::Hable => {
+                const WHITE: f32 = 11.2;
+                const EXPOSURE_BIAS: f32 = 2.0;
+                let w = hable(WHITE);
+                Color::new(
+                    hable(color.x * EXPOSURE_BIAS) / w,
+                    hable(color.y * EXPOSURE_BIAS) / w,
+                    hable(color.z * EXPOSURE_BIAS) / w,
+                )
+            }
+            ToneMap::Agx => agx(color),
+        };
+        Color::new(
+            mapped.x.clamp(0.0, 1.0),
+            mapped.y.clamp(0.0, 1.0),
+            mapped.z.clamp(0.0, 1.0),
+        )
+    }
+}
+
+/// Rec. 709 luminance.
+pub fn luminance(color: Color) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+fn scale_luminance(color: Color, curve: impl Fn(f32) -> f32) -> Color {
+    let l = luminance(color);
+    if l <= 0.0 {
+        return Color::black();
+    }
+    color * (curve(l) / l)
+}
+
+fn mul(m: &[[f32; 3]; 3], v: Vec3) -> Vec3 {
+    Vec3::new(
+        m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+        m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+        m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+    )
+}
+
+fn aces(color: Color) -> Color {
+    // sRGB to the ACES fitting space, with the RRT's saturation tweak.
+    const INPUT: [[f32; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    const OUTPUT: [[f32; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+    let fit = |v: f32| {
+        let a = v * (v + 0.0245786) - 0.000090537;
+        let b = v * (0.983729 * v + 0.432951) + 0.238081;
+        a / b
+    };
+    let v = mul(&INPUT, color);
+    mul(&OUTPUT, Vec3::new(fit(v.x), fit(v.y), fit(v.z)))
+}
+
+fn hable(x: f32) -> f32 {
+    const A: f32 = 0.15;
+    const B: f32 = 0.50;
+    const C: f32 = 0.10;
+    const D: f32 = 0.20;
+    const E: f32 = 0.02;
+    const F: f32 = 0.30;
+    (x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F) - E / F
+}
+
+fn agx(color: Color) -> Color {
+    const INSET: [[f32; 3]; 3] = [
+        [0.842_479_06, 0.078_433_6, 0.079_223_745],
+        [0.042_328_242, 0.878_468_6, 0.079_166_13],
+        [0.042_375_654, 0.078_433_6, 0.879_143],
+    ];
+    const OUTSET: [[f32; 3]; 3] = [
+        [1.196_879, -0.098_020_88, -0.099_029_74],
+        [-0.052_896_85, 1.151_903_1, -0.098_961_18],
+        [-0.052_971_635, -0.098_043_45, 1.151_073_7],
+    ];
+    const MIN_EV: f32 = -12.473_93;
+    const MAX_EV: f32 = 4.026_069;
+
+    let contrast = |x: f32| {
+        let x2 = x * x;
+        let x4 = x2 * x2;
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x
+            - 0.00232
+    };
+    let encode = |v: f32| {
+        let ev = v.max(f32::MIN_POSITIVE).log2().clamp(MIN_EV, MAX_EV);
+        contrast((ev - MIN_EV) / (MAX_EV - MIN_EV))
+    };
+
+    let v = mul(&INSET, color);
+    let v = mul(&OUTSET, Vec3::new(encode(v.x), encode(v.y), encode(v.z)));
+    // The curve's output is display encoded with a 2.2 gamma.
+    let linear = |v: f32| v.max(0.0).powf(2.2);
+    Color::new(linear(v.x), linear(v.y), linear(v.z))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPERATORS: [ToneMap; 6] = [
+        ToneMap::Clamp,
+        ToneMap::Reinhard,
+        ToneMap::ExtendedReinhard(4.0),
+        ToneMap::Aces,
+        ToneMap::Hable,
+        ToneMap::Agx,
+    ];
+
+    #[test]
+    fn bounded_and_monotonic() {
+        for op in OPERATORS {
+            let mut previous = -1.0;
+            for i in 0..200 {
+                let v = 1.1_f32.powi(i) * 1e-3;
+                let c = op.apply(Color::new(v, v, v));
+                assert!(c.x >= previous - 1e-6, "{:?} at {}", op, v);
+                assert!((0.0..=1.0).contains(&c.x));
+                assert!((c.x - c.y).abs() < 1e-3 && (c.y - c.z).abs() < 1e-3);
+                previous = c.x;
+            }
+            assert_eq!(
+                op.apply(Color::new(-1., -1., -1.)).x,
+                op.apply(Color::black()).x
+            );
+        }
+    }
+
+    #[test]
+    fn white_points() {
+        let gray = |v: f32| Color::new(v, v, v);
+
+        assert_eq!(ToneMap::Clamp.apply(gray(2.0)), gray(1.0));
+        assert!((ToneMap::Reinhard.apply(gray(1.0)).x - 0.5).abs() < 1e-6);
+        assert!((ToneMap::ExtendedReinhard(4.0).apply(gray(4.0)).x - 1.0).abs() < 1e-6);
+        assert!((ToneMap::Hable.apply(gray(5.6)).x - 1.0).abs() < 1e-6);
+        assert!(ToneMap::Aces.apply(gray(100.0)).x > 0.99);
+        assert!(ToneMap::Agx.apply(gray(100.0)).x > 0.9);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    aabb::Aabb,
+    hit_record::HitRecord,
+    interval::Interval,
+    material::Material,
+    ray::Ray,
+    vec3::{Point, Vector},
+};
+
+/// A single triangle with optional per-vertex shading normals and texture
+/// coordinates. Without them, the face is flat shaded and the UVs are the
+/// barycentric coordinates of the hit.
+pub struct Triangle {
+    pub vertices: [Point; 3],
+    pub normals: Option<[Vector; 3]>,
+    pub uvs: Option<[(f32, f32); 3]>,
+}
+
+impl Triangle {
+    pub fn new(a: Point, b: Point, c: Point) -> Self {
+        Self {
+            vertices: [a, b, c],
+            normals: None,
+            uvs: None,
+        }
+    }
+
+    pub fn hit<'a>(
+        &self,
+        ray: &Ray,
+        interval: &Interval,
+        material: &'a Material,
+    ) -> Option<HitRecord<'a>> {
+        let [p0, p1, p2] = self.vertices;
+        let e1 = p1 - p0;
+        let e2 = p2 - p0;
+
+        // Möller–Trumbore: solve for the barycentric coordinates and t at
+        // once without computing the plane equation.
+        let pvec = ray.direction.cross(e2);
+        let det = e1.dot(pvec);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+
+        let tvec = ray.origin - p0;
+        let b1 = tvec.dot(pvec) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+
+        let qvec = tvec.cross(e1);
+        let b2 = ray.direction.dot(qvec) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t = e2.dot(qvec) * inv_det;
+        if !interval.contains(t) {
+            return None;
+        }
+
+        let b0 = 1.0 - b1 - b2;
+        let geometric = e1.cross(e2).normalize();
+        let normal = match self.normals {
+            Some([n0, n1, n2]) => {
+                let n = (n0 * b0 + n1 * b1 + n2 * b2).normalize();
+                if n.x.is_finite() {
+                    n
+                } else {
+                    geometric
+                }
+            }
+            None => geometric,
+        };
+        let uv = match self.uvs {
+            Some([uv0, uv1, uv2]) => (
+                uv0.0 * b0 + uv1.0 * b1 + uv2.0 * b2,
+                uv0.1 * b0 + uv1.1 * b1 + uv2.1 * b2,
+            ),
+            None => (b1, b2),
+        };
+
+        // Which side was hit is a property of the surface, not of the
+        // interpolated normal, which can lean past the ray near silhouettes.
+        // The shading normal then follows the geometric one onto that side.
+        let mut rec = HitRecord::new(ray.at(t), geometric, t, uv, ray, material);
+        let normal = if normal.dot(geometric) < 0.0 {
+            -normal
+        } else {
+            normal
+        };
+        rec.normal = if rec.front_face { normal } else { -normal };
+        Some(rec)
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        let [a, b, c] = self.vertices;
+        Aabb::surrounding(Aabb::from_points(a, b), Aabb::from_points(a, c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn hit() {
+        let tri = Triangle::new(
+            Point::new(0., 0., 0.),
+            Point::new(1., 0., 0.),
+            Point::new(0., 1., 0.),
+        );
+        let i = Interval::new(0.001, f32::INFINITY);
+        let m = Material::Lambertian(Color::white().into());
+
+        let r = Ray::new(Point::new(0.25, 0.5, 2.), Vector::new(0., 0., -1.), 0.0);
+        let rec = tri.hit(&r, &i, &m).unwrap();
+        assert_eq!(rec.t, 2.0);
+        assert_eq!(rec.normal, Vector::new(0., 0., 1.));
+        assert_eq!((rec.u, rec.v), (0.25, 0.5));
+        assert!(rec.front_face);
+
+        let r = Ray::new(Point::new(0.75, 0.5, 2.), Vector::new(0., 0., -1.), 0.0);
+        assert!(tri.hit(&r, &i, &m).is_none());
+    }
+
+    #[test]
+    fn interpolates_normals_and_uvs() {
+        let tri = Triangle {
+            vertices: [
+                Point::new(0., 0., 0.),
+                Point::new(1., 0., 0.),
+                Point::new(0., 1., 0.),
+            ],
+            normals: Some([
+                Vector::new(0., 0., 1.),
+                Vector::new(1., 0., 0.),
+                Vector::new(0., 0., 1.),
+            ]),
+            uvs: Some([(0., 0.), (1., 0.), (1., 1.)]),
+        };
+        let m = Material::Lambertian(Color::white().into());
+
+        let r = Ray::new(Point::new(0.5, 0., 2.), Vector::new(0., 0., -1.), 0.0);
+        let rec = tri
+            .hit(&r, &Interval::new(0.001, f32::INFINITY), &m)
+            .unwrap();
+        let half = 0.5_f32.sqrt();
+        assert!((rec.normal - Vector::new(half, 0., half)).length_squared() < 1e-10);
+        assert_eq!((rec.u, rec.v), (0.5, 0.0));
+    }
+
+    #[test]
+    fn faces_by_the_geometric_normal() {
+        let leaning = Vector::new(1., 0., 0.1).normalize();
+        let mut tri = Triangle {
+            vertices: [
+                Point::new(0., 0., 0.),
+                Point::new(1., 0., 0.),
+                Point::new(0., 1., 0.),
+            ],
+            normals: Some([leaning; 3]),
+            uvs: None,
+        };
+        let m = Material::Lambertian(Color::white().into());
+        let i = Interval::new(0.001, f32::INFINITY);
+
+        // A grazing ray from the front, moving along the shading normal.
+        let r = Ray::new(Point::new(-0.75, 0.25, 0.5), Vector::new(1., 0., -0.5), 0.0);
+        let rec = tri.hit(&r, &i, &m).unwrap();
+        assert!(rec.front_face);
+        assert!((rec.normal - leaning).length_squared() < 1e-10);
+
+        // From behind, the normal is flipped toward the ray.
+        let r = Ray::new(Point::new(0.25, 0.25, -1.), Vector::new(0., 0., 1.), 0.0);
+        let rec = tri.hit(&r, &i, &m).unwrap();
+        assert!(!rec.front_face);
+        assert!((rec.normal + leaning).length_squared() < 1e-10);
+
+        // Vertex normals wound against the triangle are put on its side.
+        tri.normals = Some([Vector::new(0., 0., -1.); 3]);
+        let r = Ray::new(Point::new(0.25, 0.25, 1.), Vector::new(0., 0., -1.), 0.0);
+        let rec = tri.hit(&r, &i, &m).unwrap();
+        assert!(rec.front_face);
+        assert_eq!(rec.normal, Vector::new(0., 0., 1.));
+    }
+}
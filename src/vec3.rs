@@ -1,5 +1,5 @@
 use rand::prelude::*;
-use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::ops::{Add, Div, Index, Mul, Neg, Sub};
 
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Vec3 {
@@ -16,8 +16,7 @@ impl Vec3 {
         Self { x, y, z }
     }
 
-    pub fn random() -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn random(rng: &mut impl Rng) -> Self {
         Self {
             x: rng.gen::<f32>(),
             y: rng.gen::<f32>(),
@@ -25,8 +24,7 @@ impl Vec3 {
         }
     }
 
-    pub fn random_in(min: f32, max: f32) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn random_in(rng: &mut impl Rng, min: f32, max: f32) -> Self {
         Self {
             x: rng.gen_range(min..max),
             y: rng.gen_range(min..max),
@@ -34,9 +32,9 @@ impl Vec3 {
         }
     }
 
-    pub fn random_normalized() -> Self {
+    pub fn random_normalized(rng: &mut impl Rng) -> Self {
         loop {
-            let p = Self::random_in(-1., 1.);
+            let p = Self::random_in(rng, -1., 1.);
             let lensq = p.length_squared();
             if 1e-160 < lensq && lensq <= 1.0 {
                 break p.normalize();
@@ -44,17 +42,6 @@ impl Vec3 {
         }
     }
 
-    pub fn random_in_unit_disk() -> Self {
-        loop {
-            let mut rng = rand::thread_rng();
-            let p = Self::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), 0.0);
-
-            if p.length_squared() < 1.0 {
-                break p;
-            }
-        }
-    }
-
     pub fn dot(self, rhs: Self) -> f32 {
         self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
     }
@@ -94,6 +81,18 @@ impl Vec3 {
         let r_out_parallel = normal * -(1.0 - r_out_perp.length_squared()).abs().sqrt();
         r_out_perp + r_out_parallel
     }
+
+    /// Two unit vectors that, with this unit vector as the third axis,
+    /// form a right-handed orthonormal basis (Duff et al. 2017).
+    pub fn orthonormal_basis(self) -> (Self, Self) {
+        let sign = 1f32.copysign(self.z);
+        let a = -1.0 / (sign + self.z);
+        let b = self.x * self.y * a;
+        (
+            Self::new(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            Self::new(b, sign + self.y * self.y * a, -self.y),
+        )
+    }
 }
 
 impl Neg for Vec3 {
@@ -138,6 +137,171 @@ impl Div<f32> for Vec3 {
     }
 }
 
+impl Index<usize> for Vec3 {
+    type Output = f32;
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vec3 index out of range: {}", index),
+        }
+    }
+}
+
+/// A row-major 4x4 matrix for affine transforms of points and vectors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f32; 4]; 4],
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mat4 {
+    pub fn new(m: [[f32; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    pub fn identity() -> Self {
+        Self::new([
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn translation(offset: Vector) -> Self {
+        Self::new([
+            [1., 0., 0., offset.x],
+            [0., 1., 0., offset.y],
+            [0., 0., 1., offset.z],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn scaling(factors: Vector) -> Self {
+        Self::new([
+            [factors.x, 0., 0., 0.],
+            [0., factors.y, 0., 0.],
+            [0., 0., factors.z, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    /// Counter-clockwise rotation by `degrees` around `axis`, looking down
+    /// the axis towards the origin.
+    pub fn rotation(axis: Vector, degrees: f32) -> Self {
+        let a = axis.normalize();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let t = 1.0 - cos;
+        Self::new([
+            [
+                t * a.x * a.x + cos,
+                t * a.x * a.y - sin * a.z,
+                t * a.x * a.z + sin * a.y,
+                0.,
+            ],
+            [
+                t * a.x * a.y + sin * a.z,
+                t * a.y * a.y + cos,
+                t * a.y * a.z - sin * a.x,
+                0.,
+            ],
+            [
+                t * a.x * a.z - sin * a.y,
+                t * a.y * a.z + sin * a.x,
+                t * a.z * a.z + cos,
+                0.,
+            ],
+            [0., 0., 0., 1.],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Self::new(m)
+    }
+
+    /// Gauss-Jordan elimination with partial pivoting. Returns `None` for
+    /// singular matrices.
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::identity().m;
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row != col {
+                    let factor = a[row][col];
+                    for j in 0..4 {
+                        a[row][j] -= factor * a[col][j];
+                        inv[row][j] -= factor * inv[col][j];
+                    }
+                }
+            }
+        }
+        Some(Self::new(inv))
+    }
+
+    pub fn transform_point(&self, p: Point) -> Point {
+        let m = &self.m;
+        let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+        let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+        let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+        if w == 1.0 {
+            Point::new(x, y, z)
+        } else {
+            Point::new(x, y, z) / w
+        }
+    }
+
+    pub fn transform_vector(&self, v: Vector) -> Vector {
+        let m = &self.m;
+        Vector::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Mat4::new(m)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,6 +347,15 @@ mod tests {
         assert_eq!(a / 2.0, Vec3::new(0.5, 1., 1.5));
     }
 
+    #[test]
+    fn index() {
+        let a = Vec3::new(1., 2., 3.);
+
+        assert_eq!(a[0], 1.);
+        assert_eq!(a[1], 2.);
+        assert_eq!(a[2], 3.);
+    }
+
     #[test]
     fn length_squared() {
         let a = Vec3::new(1., 2., 3.);
@@ -221,4 +394,67 @@ mod tests {
         assert_eq!(Vec3::cross(a, b), Vec3::new(-1., 2., -1.));
         assert_eq!(Vec3::cross(b, a), Vec3::new(1., -2., 1.));
     }
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!((a - b).length_squared() < 1e-10, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn mat4_multiply() {
+        let t = Mat4::translation(Vector::new(1., 2., 3.));
+        let s = Mat4::scaling(Vector::new(2., 2., 2.));
+
+        assert_eq!(t * Mat4::identity(), t);
+        assert_eq!(
+            (t * s).transform_point(Point::new(1., 1., 1.)),
+            Point::new(3., 4., 5.)
+        );
+        assert_eq!(
+            (s * t).transform_point(Point::new(1., 1., 1.)),
+            Point::new(4., 6., 8.)
+        );
+    }
+
+    #[test]
+    fn mat4_transform_vector_ignores_translation() {
+        let t = Mat4::translation(Vector::new(1., 2., 3.));
+
+        assert_eq!(
+            t.transform_vector(Vector::new(1., 0., 0.)),
+            Vector::new(1., 0., 0.)
+        );
+    }
+
+    #[test]
+    fn mat4_rotation() {
+        let r = Mat4::rotation(Vector::new(0., 1., 0.), 90.0);
+
+        assert_near(
+            r.transform_vector(Vector::new(1., 0., 0.)),
+            Vector::new(0., 0., -1.),
+        );
+        assert_near(
+            r.transform_vector(Vector::new(0., 0., 1.)),
+            Vector::new(1., 0., 0.),
+        );
+    }
+
+    #[test]
+    fn mat4_inverse() {
+        let m = Mat4::translation(Vector::new(1., -2., 3.))
+            * Mat4::rotation(Vector::new(1., 1., 0.), 30.0)
+            * Mat4::scaling(Vector::new(2., 0.5, 3.));
+        let inv = m.inverse().unwrap();
+        let p = Point::new(0.3, -1.2, 4.);
+
+        assert_near(inv.transform_point(m.transform_point(p)), p);
+        assert!(Mat4::scaling(Vector::new(1., 0., 1.)).inverse().is_none());
+    }
+
+    #[test]
+    fn mat4_transpose() {
+        let t = Mat4::translation(Vector::new(1., 2., 3.)).transpose();
+
+        assert_eq!(t.m[3], [1., 2., 3., 1.]);
+    }
 }
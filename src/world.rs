@@ -1,36 +1,85 @@
+use std::sync::OnceLock;
+
 use crate::{
-    entity::Entity, hit_record::HitRecord, hittable::Hittable, interval::Interval, ray::Ray,
+    aabb::Aabb,
+    bvh::Bvh,
+    entity::Entity,
+    hit_record::HitRecord,
+    hittable::Hittable,
+    interval::Interval,
+    light::Light,
+    ray::Ray,
+    vec3::{Point, Vector},
 };
 
 #[derive(Default)]
 pub struct World {
-    pub entities: Vec<Entity>,
+    entities: Vec<Entity>,
+    bvh: OnceLock<Bvh>,
+    lights: OnceLock<Vec<Light>>,
 }
 
 impl World {
     pub fn new() -> Self {
-        Self { entities: vec![] }
+        Self::default()
     }
 
     pub fn add(&mut self, entity: Entity) {
         self.entities.push(entity);
+        self.bvh = OnceLock::new();
+        self.lights = OnceLock::new();
     }
-}
 
-impl Hittable for World {
-    fn hit(&self, r: &Ray, interval: &Interval) -> Option<HitRecord> {
-        let mut hit_record: Option<HitRecord> = None;
-        let mut closest = interval.max;
-
-        for entity in &self.entities {
-            match entity.hit(r, &Interval::new(interval.min, closest)) {
-                None => {}
-                Some(rec) => {
-                    closest = rec.t;
-                    hit_record = Some(rec);
-                }
+    /// The entities added so far, in order.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// Builds the bounding volume hierarchy over the current entities.
+    /// `Camera::render` calls this before rendering; adding an entity
+    /// afterwards discards the tree so it is rebuilt on the next call.
+    pub fn build_bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            let boxes: Vec<Aabb> = self.entities.iter().map(|e| e.bounding_box()).collect();
+            Bvh::new(&boxes)
+        })
+    }
+
+    /// The emissive spheres and quads that can be sampled directly, found
+    /// on first use like the BVH.
+    pub fn lights(&self) -> &[Light] {
+        self.lights.get_or_init(|| {
+            let mut lights = vec![];
+            for entity in &self.entities {
+                Light::collect(entity, &mut lights);
             }
+            lights
+        })
+    }
+
+    /// Density of sampling `direction` from `origin` by picking one of the
+    /// lights uniformly and then a point on it.
+    pub fn light_pdf(&self, origin: Point, direction: Vector, time: f32) -> f32 {
+        let lights = self.lights();
+        if lights.is_empty() {
+            return 0.0;
         }
-        hit_record
+        let total: f32 = lights
+            .iter()
+            .map(|light| light.pdf(origin, direction, time))
+            .sum();
+        total / lights.len() as f32
+    }
+}
+
+impl Hittable for World {
+    fn hit(&self, r: &Ray, interval: &Interval) -> Option<HitRecord<'_>> {
+        self.build_bvh().hit(r, interval, |i, r, interval| {
+            self.entities[i].hit(r, interval)
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.build_bvh().bounding_box()
     }
 }
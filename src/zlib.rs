@@ -0,0 +1,265 @@
+//! Just enough of zlib (RFC 1950) and deflate (RFC 1951) to write
+//! compressed image files without pulling in a dependency.
+
+const WINDOW_SIZE: usize = 1 << 15;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+/// How many earlier positions with the same hash to try before settling
+/// for the best match so far.
+const MAX_CHAIN: usize = 64;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Wraps `data` in a zlib stream holding a single deflate block coded with
+/// the fixed Huffman tables.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter::default();
+    // CM = 8 (deflate) with a 32K window, no dictionary, fastest level;
+    // 0x7801 is a multiple of 31 as the header check requires.
+    out.bytes.extend_from_slice(&[0x78, 0x01]);
+
+    // BFINAL, then BTYPE = 01 (fixed Huffman).
+    out.write(1, 1);
+    out.write(1, 2);
+    deflate(data, &mut out);
+    write_literal(&mut out, 256);
+    out.flush();
+
+    out.bytes.extend_from_slice(&adler32(data).to_be_bytes());
+    out.bytes
+}
+
+/// Wraps `data` in a zlib stream of uncompressed blocks.
+pub fn store(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        out.push(blocks.peek().is_none() as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the most bytes that can be summed before b overflows.
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Greedy LZ77 over hash chains of three-byte prefixes.
+fn deflate(data: &[u8], out: &mut BitWriter) {
+    let hash = |i: usize| {
+        let key = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+        (key.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+    };
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(i);
+            prev[i % WINDOW_SIZE] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let (length, distance) = longest_match(data, i, &head, &prev, hash);
+        if length >= MIN_MATCH {
+            write_match(out, length, distance);
+            for j in i..i + length {
+                insert(j, &mut head, &mut prev);
+            }
+            i += length;
+        } else {
+            write_literal(out, data[i] as u16);
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+}
+
+fn longest_match(
+    data: &[u8],
+    i: usize,
+    head: &[usize],
+    prev: &[usize],
+    hash: impl Fn(usize) -> usize,
+) -> (usize, usize) {
+    if i + MIN_MATCH > data.len() {
+        return (0, 0);
+    }
+    let max = MAX_MATCH.min(data.len() - i);
+    let (mut best_length, mut best_distance) = (0, 0);
+    let mut candidate = head[hash(i)];
+    for _ in 0..MAX_CHAIN {
+        if candidate == usize::MAX || i - candidate > WINDOW_SIZE - 1 {
+            break;
+        }
+        let length = data[candidate..]
+            .iter()
+            .zip(&data[i..i + max])
+            .take_while(|(a, b)| a == b)
+            .count();
+        if length > best_length {
+            (best_length, best_distance) = (length, i - candidate);
+            if length == max {
+                break;
+            }
+        }
+        let next = prev[candidate % WINDOW_SIZE];
+        // Slots are reused as the window slides, so stop at anything that
+        // isn't strictly older.
+        if next == usize::MAX || next >= candidate {
+            break;
+        }
+        candidate = next;
+    }
+    (best_length, best_distance)
+}
+
+fn write_literal(out: &mut BitWriter, symbol: u16) {
+    let (code, len) = match symbol {
+        0..=143 => (0x30 + symbol, 8),
+        144..=255 => (0x190 + symbol - 144, 9),
+        256..=279 => (symbol - 256, 7),
+        _ => (0xc0 + symbol - 280, 8),
+    };
+    out.write_huffman(code as u32, len);
+}
+
+fn write_match(out: &mut BitWriter, length: usize, distance: usize) {
+    let l = LENGTH_BASE
+        .iter()
+        .rposition(|&b| b as usize <= length)
+        .unwrap();
+    write_literal(out, 257 + l as u16);
+    out.write(
+        (length - LENGTH_BASE[l] as usize) as u32,
+        LENGTH_EXTRA[l] as u32,
+    );
+
+    let d = DISTANCE_BASE
+        .iter()
+        .rposition(|&b| b as usize <= distance)
+        .unwrap();
+    out.write_huffman(d as u32, 5);
+    out.write(
+        (distance - DISTANCE_BASE[d] as usize) as u32,
+        DISTANCE_EXTRA[d] as u32,
+    );
+}
+
+/// Packs bits least significant first, as deflate requires.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u32) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes are defined most significant bit first.
+    fn write_huffman(&mut self, code: u32, bits: u32) {
+        self.write(code.reverse_bits() >> (32 - bits), bits);
+    }
+
+    fn flush(&mut self) {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer = 0;
+            self.count = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        // Long enough to need the periodic reduction.
+        assert_eq!(adler32(&[0xff; 100_000]), 0x149a_302c);
+    }
+
+    #[test]
+    fn store() {
+        let stream = super::store(b"abc");
+
+        assert_eq!(&stream[..7], &[0x78, 0x01, 1, 3, 0, 0xfc, 0xff]);
+        assert_eq!(&stream[7..10], b"abc");
+        assert_eq!(&stream[10..], &adler32(b"abc").to_be_bytes());
+    }
+
+    #[test]
+    fn compress() {
+        // "a" as a literal, then a match of length 5 at distance 1, then
+        // end of block, all with the fixed codes.
+        let stream = super::compress(b"aaaaaa");
+
+        assert_eq!(&stream[..2], &[0x78, 0x01]);
+        assert_eq!(&stream[2..stream.len() - 4], &[0x4b, 0x04, 0x03, 0x00]);
+
+        let repetitive: Vec<u8> = (0..10_000).map(|i| (i % 7) as u8).collect();
+        assert!(super::compress(&repetitive).len() < 100);
+    }
+}